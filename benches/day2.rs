@@ -0,0 +1,104 @@
+//! Compares executing day 2 instructions with the original implementation scanning the layout
+//! string, walking the keypad grid and the precomputed transition table on generated inputs
+//!
+//! ```bash
+//! cargo bench --bench day2
+//! ```
+
+use adventofcode_rust_2016::day2::{execute, Instr, Keypad, KEYPAD_1, KEYPAD_2};
+use adventofcode_rust_2016::random::xorshift;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// deterministic pseudo random instructions, `lines` lines of `moves` moves each
+fn generate_input(lines: usize, moves: usize) -> Vec<Vec<Instr>> {
+    let mut next = xorshift(0x2016_0002);
+    (0..lines)
+        .map(|_| {
+            (0..moves)
+                .map(|_| match next() % 4 {
+                    0 => Instr::Up,
+                    1 => Instr::Down,
+                    2 => Instr::Left,
+                    _ => Instr::Right,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// the original implementation of `keypad_get`, scanning the layout string for every move
+fn keypad_get_str(keypad: &str, pos: (i64, i64)) -> Option<char> {
+    if pos.0 < 0 || pos.1 < 0 {
+        return None;
+    }
+    let line = keypad.lines().nth(pos.0 as usize)?;
+    let key = line.chars().nth(pos.1 as usize)?;
+    if key != ' ' {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// the original implementation of `execute`
+fn execute_str(input: &[Vec<Instr>], keypad: &str, start: char) -> String {
+    let mut code = String::new();
+    let mut pos = (0, 0);
+    for (row, line) in keypad.lines().enumerate() {
+        if let Some(col) = line.find(start) {
+            pos.0 = row as i64;
+            pos.1 = col as i64;
+            break;
+        }
+    }
+    for line in input {
+        for instr in line {
+            let vec = match instr {
+                Instr::Up => (-1, 0),
+                Instr::Down => (1, 0),
+                Instr::Left => (0, -1),
+                Instr::Right => (0, 1),
+                _ => unreachable!("the original only knows orthogonal moves"),
+            };
+            let target = (pos.0 + vec.0, pos.1 + vec.1);
+            if keypad_get_str(keypad, target).is_some() {
+                pos = target;
+            }
+        }
+        code += &keypad_get_str(keypad, pos).unwrap().to_string();
+    }
+    code
+}
+
+fn bench_execute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day2");
+    group.sample_size(10);
+    for moves in [100_000, 1_000_000, 5_000_000] {
+        let input = generate_input(100, moves / 100);
+        group.throughput(Throughput::Elements(moves as u64));
+        for (name, layout) in [("keypad1", KEYPAD_1), ("keypad2", KEYPAD_2)] {
+            let keypad = Keypad::new(layout, '5').unwrap();
+            let table = keypad.transitions();
+            assert_eq!(execute_str(&input, layout, '5'), table.execute(&input));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name}/string"), moves),
+                &input,
+                |b, input| b.iter(|| execute_str(input, layout, '5')),
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name}/grid"), moves),
+                &input,
+                |b, input| b.iter(|| execute(input, &keypad)),
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name}/table"), moves),
+                &input,
+                |b, input| b.iter(|| table.execute(input)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_execute);
+criterion_main!(benches);
@@ -0,0 +1,102 @@
+//! Compares validating day 4 rooms with fixed letter counts against the previous implementation
+//! built on hash maps and intermediate strings, on a million generated rooms
+//!
+//! ```bash
+//! cargo bench --bench day4
+//! ```
+
+use adventofcode_rust_2016::day4::Room;
+use adventofcode_rust_2016::random::xorshift;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+const ROOMS: usize = 1_000_000;
+
+/// deterministic pseudo random rooms, half of them decoys
+fn generate_rooms(count: usize) -> Vec<Room> {
+    let mut next = xorshift(0x2016_0004);
+    (0..count)
+        .map(|_| {
+            let words: Vec<String> = (0..2 + next() % 4)
+                .map(|_| {
+                    (0..3 + next() % 8)
+                        .map(|_| (b'a' + (next() % 26) as u8) as char)
+                        .collect()
+                })
+                .collect();
+            let mut room = Room {
+                name: words.join("-"),
+                sector_id: 100 + (next() % 900) as u32,
+                checksum: String::new(),
+            };
+            room.checksum = if next().is_multiple_of(2) {
+                room.compute_checksum()
+            } else {
+                (0..5)
+                    .map(|_| (b'a' + (next() % 26) as u8) as char)
+                    .collect()
+            };
+            room
+        })
+        .collect()
+}
+
+/// the previous implementation of `Room::is_valid`
+fn is_valid_hashmap(room: &Room) -> bool {
+    let mut count_map: HashMap<char, u32> = HashMap::new();
+    for c in room.name.replace('-', "").chars() {
+        if let Entry::Vacant(e) = count_map.entry(c) {
+            e.insert(1);
+        } else {
+            let val = count_map.get_mut(&c).unwrap();
+            *val += 1;
+        }
+    }
+    let counts: HashSet<u32> = count_map.values().copied().collect();
+    let mut counts: Vec<u32> = counts.iter().copied().collect();
+    counts.sort_unstable();
+    counts.reverse();
+
+    let mut checksum = String::new();
+
+    for count in counts {
+        let mut part = String::new();
+        for c in count_map.keys() {
+            if *count_map.get(c).unwrap() == count {
+                part += &c.to_string();
+            }
+        }
+        let mut chars: Vec<char> = part.chars().collect();
+        chars.sort_unstable();
+        checksum += &String::from_iter(chars);
+        if checksum.len() > 5 {
+            checksum = checksum[0..5].to_string();
+        }
+        if checksum.len() == 5 {
+            break;
+        }
+    }
+    room.checksum.eq(&checksum)
+}
+
+fn bench_is_valid(c: &mut Criterion) {
+    let rooms = generate_rooms(ROOMS);
+    assert!(rooms
+        .iter()
+        .all(|room| room.is_valid() == is_valid_hashmap(room)));
+
+    let mut group = c.benchmark_group("day4");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(ROOMS as u64));
+    group.bench_function("hashmap", |b| {
+        b.iter(|| rooms.iter().filter(|room| is_valid_hashmap(room)).count())
+    });
+    group.bench_function("counts", |b| {
+        b.iter(|| rooms.iter().filter(|room| room.is_valid()).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_is_valid);
+criterion_main!(benches);
@@ -0,0 +1,189 @@
+//! # [Shift Cipher](https://en.wikipedia.org/wiki/Caesar_cipher)
+//!
+//! Rotates letters through the alphabet, preserving case and leaving everything else untouched.
+//!
+//! Without the key, the ciphertext is decrypted with all 26 possible keys and the candidate
+//! containing the most words of a dictionary wins. Ties (or texts without any known words) are
+//! broken by how close the letter frequencies are to English, measured by
+//! [Pearson's chi-squared test](https://en.wikipedia.org/wiki/Pearson%27s_chi-squared_test).
+
+/// relative frequencies of `a..=z` in English text
+const ENGLISH: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015, 0.06094, 0.06966, 0.00153,
+    0.00772, 0.04025, 0.02406, 0.06749, 0.07507, 0.01929, 0.00095, 0.05987, 0.06327, 0.09056,
+    0.02758, 0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
+];
+
+/// words which show up in the room names of Easter Bunny HQ, plus some common English ones
+pub const DICTIONARY: &[&str] = &[
+    "a",
+    "and",
+    "basket",
+    "biohazardous",
+    "bunny",
+    "candy",
+    "center",
+    "chocolate",
+    "classified",
+    "containment",
+    "corrosive",
+    "cryogenic",
+    "customer",
+    "department",
+    "deployment",
+    "design",
+    "dye",
+    "egg",
+    "engineering",
+    "experimental",
+    "flower",
+    "fuzzy",
+    "grade",
+    "grass",
+    "hunt",
+    "is",
+    "jellybean",
+    "laboratory",
+    "magnetic",
+    "military",
+    "name",
+    "northpole",
+    "object",
+    "of",
+    "operations",
+    "plastic",
+    "projects",
+    "purchasing",
+    "rabbit",
+    "radioactive",
+    "reindeer",
+    "research",
+    "scavenger",
+    "secret",
+    "storage",
+    "technology",
+    "the",
+    "top",
+    "training",
+    "user",
+    "weaponized",
+];
+
+/// rotates every letter `key` places forward through the alphabet, `Z` becomes `A`
+pub fn encrypt(text: &str, key: u32) -> String {
+    text.chars().map(|c| rotate(c, key)).collect()
+}
+
+/// rotates every letter `key` places backwards through the alphabet, undoing [`encrypt`]
+pub fn decrypt(text: &str, key: u32) -> String {
+    encrypt(text, 26 - key % 26)
+}
+
+/// [`encrypt`] for a single character
+pub fn rotate(c: char, key: u32) -> char {
+    let base = match c {
+        'a'..='z' => b'a',
+        'A'..='Z' => b'A',
+        _ => return c,
+    };
+    ((c as u8 - base + (key % 26) as u8) % 26 + base) as char
+}
+
+/// chi-squared statistic of the letter frequencies in `text` against English, lower is more
+/// English-like. Text without letters scores `0`.
+pub fn chi_squared(text: &str) -> f64 {
+    let mut counts = [0u32; 26];
+    for c in text.chars().filter(char::is_ascii_alphabetic) {
+        counts[(c.to_ascii_lowercase() as u8 - b'a') as usize] += 1;
+    }
+    let total: u32 = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .iter()
+        .zip(ENGLISH)
+        .map(|(&observed, frequency)| {
+            let expected = frequency * total as f64;
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+/// A decryption found without knowing the key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    /// `decrypt(ciphertext, key) == plaintext`
+    pub key: u32,
+    pub plaintext: String,
+    /// how many words of the plaintext are in the dictionary
+    pub words: usize,
+    pub chi_squared: f64,
+}
+
+/// tries every key and keeps the plaintext with the most `dictionary` words, ties broken by
+/// [`chi_squared`]. Words are compared case-insensitive.
+pub fn recover(ciphertext: &str, dictionary: &[&str]) -> Recovered {
+    (0..26)
+        .map(|key| {
+            let plaintext = decrypt(ciphertext, key);
+            let words = plaintext
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .filter(|word| {
+                    dictionary
+                        .iter()
+                        .any(|known| known.eq_ignore_ascii_case(word))
+                })
+                .count();
+            let chi_squared = chi_squared(&plaintext);
+            Recovered {
+                key,
+                plaintext,
+                words,
+                chi_squared,
+            }
+        })
+        .min_by(|a, b| {
+            b.words
+                .cmp(&a.words)
+                .then(a.chi_squared.total_cmp(&b.chi_squared))
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(encrypt("Hello, World! 42", 3), "Khoor, Zruog! 42");
+        assert_eq!(decrypt("Khoor, Zruog! 42", 3), "Hello, World! 42");
+        assert_eq!(encrypt("xyz XYZ", 29), "abc ABC");
+        assert_eq!(decrypt("abc", 0), "abc");
+        for key in 0..60 {
+            let text = "The quick brown Fox jumps over the lazy Dog.";
+            assert_eq!(decrypt(&encrypt(text, key), key), text);
+        }
+    }
+
+    #[test]
+    fn recover_key() {
+        // enough text for the letter frequencies alone
+        let text = "It was the best of times, it was the worst of times, it was the age of \
+                    wisdom, it was the age of foolishness";
+        let recovered = recover(&encrypt(text, 11), &[]);
+        assert_eq!(recovered.key, 11);
+        assert_eq!(recovered.plaintext, text);
+        assert_eq!(recovered.words, 0);
+
+        // short names need the dictionary
+        let recovered = recover(&encrypt("very encrypted name", 7), DICTIONARY);
+        assert_eq!(recovered.key, 7);
+        assert_eq!(recovered.plaintext, "very encrypted name");
+        assert_eq!(recovered.words, 1);
+
+        assert_eq!(recover("", DICTIONARY).key, 0);
+        assert_eq!(chi_squared("42"), 0.0);
+    }
+}
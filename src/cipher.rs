@@ -0,0 +1,231 @@
+//! # [Classical Ciphers](https://en.wikipedia.org/wiki/Classical_cipher)
+//!
+//! Substitution ciphers behind a common [`Cipher`] trait. All of them work on `a..=z` and
+//! `A..=Z`, preserve case and leave every other character untouched.
+
+use crate::caesar;
+use anyhow::{bail, Result};
+
+pub trait Cipher {
+    fn encrypt(&self, text: &str) -> String;
+    fn decrypt(&self, text: &str) -> String;
+}
+
+impl<C: Cipher + ?Sized> Cipher for Box<C> {
+    fn encrypt(&self, text: &str) -> String {
+        (**self).encrypt(text)
+    }
+
+    fn decrypt(&self, text: &str) -> String {
+        (**self).decrypt(text)
+    }
+}
+
+/// [Caesar cipher](https://en.wikipedia.org/wiki/Caesar_cipher), rotates every letter `key`
+/// places forward through the alphabet, see [`caesar`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shift {
+    pub key: u32,
+}
+
+impl Cipher for Shift {
+    fn encrypt(&self, text: &str) -> String {
+        caesar::encrypt(text, self.key)
+    }
+
+    fn decrypt(&self, text: &str) -> String {
+        caesar::decrypt(text, self.key)
+    }
+}
+
+/// [Vigenère cipher](https://en.wikipedia.org/wiki/Vigen%C3%A8re_cipher), shifts every letter
+/// by the next letter of the key (`a` = 0). Only letters of the text use up the key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vigenere {
+    /// shifts `0..26`
+    key: Vec<u32>,
+}
+
+impl Vigenere {
+    /// the key must only consist of letters
+    pub fn new(key: &str) -> Result<Vigenere> {
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic()) {
+            bail!("key must be letters only, got {:?}", key);
+        }
+        let key = key
+            .bytes()
+            .map(|c| (c.to_ascii_lowercase() - b'a') as u32)
+            .collect();
+        Ok(Vigenere { key })
+    }
+
+    fn apply(&self, text: &str, shift: impl Fn(u32) -> u32) -> String {
+        let mut key = self.key.iter().cycle();
+        text.chars()
+            .map(|c| {
+                if c.is_ascii_alphabetic() {
+                    caesar::rotate(c, shift(*key.next().unwrap()))
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+}
+
+impl Cipher for Vigenere {
+    fn encrypt(&self, text: &str) -> String {
+        self.apply(text, |key| key)
+    }
+
+    fn decrypt(&self, text: &str) -> String {
+        self.apply(text, |key| 26 - key)
+    }
+}
+
+/// [Affine cipher](https://en.wikipedia.org/wiki/Affine_cipher), maps letter `x` to
+/// `(a * x + b) mod 26`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Affine {
+    a: u32,
+    b: u32,
+    /// multiplicative inverse of `a` modulo 26
+    a_inv: u32,
+}
+
+impl Affine {
+    /// `a` must be coprime to 26, otherwise different letters would encrypt to the same one
+    pub fn new(a: u32, b: u32) -> Result<Affine> {
+        match (1..26).find(|a_inv| (a % 26 * a_inv) % 26 == 1) {
+            Some(a_inv) => Ok(Affine {
+                a: a % 26,
+                b: b % 26,
+                a_inv,
+            }),
+            None => bail!("a must be coprime to 26, got {}", a),
+        }
+    }
+
+    fn apply(&self, text: &str, map: impl Fn(u32) -> u32) -> String {
+        text.chars()
+            .map(|c| {
+                let base = match c {
+                    'a'..='z' => b'a',
+                    'A'..='Z' => b'A',
+                    _ => return c,
+                };
+                (map((c as u8 - base) as u32) as u8 + base) as char
+            })
+            .collect()
+    }
+}
+
+impl Cipher for Affine {
+    fn encrypt(&self, text: &str) -> String {
+        self.apply(text, |x| (self.a * x + self.b) % 26)
+    }
+
+    fn decrypt(&self, text: &str) -> String {
+        self.apply(text, |y| self.a_inv * (y + 26 - self.b) % 26)
+    }
+}
+
+/// shifts every word by its own key, cycling through `keys`. Words are runs of letters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotPerWord {
+    keys: Vec<u32>,
+}
+
+impl RotPerWord {
+    pub fn new(keys: &[u32]) -> Result<RotPerWord> {
+        if keys.is_empty() {
+            bail!("at least one key is needed");
+        }
+        Ok(RotPerWord {
+            keys: keys.to_vec(),
+        })
+    }
+
+    fn apply(&self, text: &str, shift: impl Fn(u32) -> u32) -> String {
+        let mut keys = self.keys.iter().cycle();
+        let mut key = 0;
+        let mut in_word = false;
+        text.chars()
+            .map(|c| {
+                let is_letter = c.is_ascii_alphabetic();
+                if is_letter && !in_word {
+                    key = shift(*keys.next().unwrap());
+                }
+                in_word = is_letter;
+                caesar::rotate(c, key)
+            })
+            .collect()
+    }
+}
+
+impl Cipher for RotPerWord {
+    fn encrypt(&self, text: &str) -> String {
+        self.apply(text, |key| key)
+    }
+
+    fn decrypt(&self, text: &str) -> String {
+        self.apply(text, |key| 26 - key % 26)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Northpole object-storage, 42 Rooms!";
+
+    fn round_trip(cipher: &dyn Cipher) {
+        let encrypted = cipher.encrypt(TEXT);
+        assert_ne!(encrypted, TEXT);
+        assert_eq!(cipher.decrypt(&encrypted), TEXT);
+    }
+
+    #[test]
+    fn shift() {
+        let cipher = Shift { key: 3 };
+        assert_eq!(cipher.encrypt("Hello, World"), "Khoor, Zruog");
+        for key in 1..60 {
+            if key % 26 != 0 {
+                round_trip(&Shift { key });
+            }
+        }
+    }
+
+    #[test]
+    fn vigenere() {
+        let cipher = Vigenere::new("LEMON").unwrap();
+        assert_eq!(cipher.encrypt("ATTACK AT DAWN"), "LXFOPV EF RNHR");
+        assert_eq!(cipher.decrypt("lxfopv ef rnhr"), "attack at dawn");
+        round_trip(&cipher);
+        round_trip(&Vigenere::new("z").unwrap());
+        assert!(Vigenere::new("").is_err());
+        assert!(Vigenere::new("key 2").is_err());
+    }
+
+    #[test]
+    fn affine() {
+        let cipher = Affine::new(5, 8).unwrap();
+        assert_eq!(cipher.encrypt("AFFINE cipher"), "IHHWVC swfrcp");
+        assert_eq!(cipher.decrypt("IHHWVC swfrcp"), "AFFINE cipher");
+        for a in [1, 3, 5, 7, 9, 11, 15, 17, 19, 21, 23, 25] {
+            round_trip(&Affine::new(a, 7).unwrap());
+        }
+        assert!(Affine::new(13, 1).is_err());
+        assert!(Affine::new(4, 1).is_err());
+    }
+
+    #[test]
+    fn rot_per_word() {
+        let cipher = RotPerWord::new(&[1, 2]).unwrap();
+        assert_eq!(cipher.encrypt("abc abc-abc, z"), "bcd cde-bcd, b");
+        assert_eq!(cipher.decrypt("bcd cde-bcd, b"), "abc abc-abc, z");
+        round_trip(&cipher);
+        round_trip(&RotPerWord::new(&[13]).unwrap());
+        assert!(RotPerWord::new(&[]).is_err());
+    }
+}
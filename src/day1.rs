@@ -26,7 +26,7 @@
 //!
 //! -   Following `R2, L3` leaves you `2` blocks East and `3` blocks North, or `5` blocks away.
 //! -   `R2, R2, R2` leaves you `2` blocks due South of your starting position, which is `2`
-//!      blocks away.
+//!     blocks away.
 //! -   `R5, L5, R5, R3` leaves you `12` blocks away.
 //!
 //! **How many blocks away is Easter Bunny HQ?**
@@ -41,124 +41,731 @@
 //!
 //! How many blocks away is the first location you visit twice?
 
+use anyhow::{anyhow, bail};
 use num_derive::FromPrimitive;
 use num_derive::ToPrimitive;
 use num_traits::FromPrimitive;
 use num_traits::ToPrimitive;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fmt::Debug;
+use std::str::FromStr;
 
 #[aoc_generator(day1)]
-fn parse_input(input: &str) -> Vec<Instr> {
+pub fn parse_input(input: &str) -> Vec<Instr> {
     input
-        .split(", ")
-        .map(|part| match part.chars().next().unwrap() {
-            'L' => Instr::Left(part[1..].parse().unwrap()),
-            'R' => Instr::Right(part[1..].parse().unwrap()),
-            _ => panic!("invalid input"),
-        })
+        .split(',')
+        .map(|part| part.trim().parse().expect("invalid input"))
         .collect()
 }
 
 /// Part 1: How many blocks away is Easter Bunny HQ?
 #[aoc(day1, part1)]
-fn part1(input: &Vec<Instr>) -> u64 {
-    let pos = execute1(input);
-    manhattan_distance(pos)
+fn part1(input: &[Instr]) -> u64 {
+    manhattan_distance(execute1(input))
 }
 
 /// Part 2: How many blocks away is the first location you visit twice?
 #[aoc(day1, part2)]
-fn part2(input: &Vec<Instr>) -> u64 {
-    let pos = execute2(input).unwrap();
-    manhattan_distance(pos)
+fn part2(input: &[Instr]) -> u64 {
+    manhattan_distance(execute2(input).unwrap())
 }
 
-#[derive(FromPrimitive, ToPrimitive, Debug)]
-enum Direction {
+/// Heading of the walker, listed clockwise in steps of 45 degrees
+#[derive(FromPrimitive, ToPrimitive, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
     North = 0,
-    East = 1,
-    South = 2,
-    West = 3,
+    NorthEast = 1,
+    East = 2,
+    SouthEast = 3,
+    South = 4,
+    SouthWest = 5,
+    West = 6,
+    NorthWest = 7,
+}
+
+/// A heading on a grid: how instructions turn it and where a single block leads
+pub trait Heading: Copy + Debug + PartialEq {
+    fn left(&self) -> Self;
+    fn right(&self) -> Self;
+    /// turns 45 degrees, `None` if the grid has no heading in between
+    fn half_left(&self) -> Option<Self>;
+    fn half_right(&self) -> Option<Self>;
+    fn back(&self) -> Self;
+    fn vec(&self) -> (i64, i64);
 }
 
 impl Direction {
+    /// turns clockwise by `eighths` of a full turn
+    fn turn(&self, eighths: u8) -> Direction {
+        let val = (ToPrimitive::to_u8(self).unwrap() + eighths) % 8;
+        FromPrimitive::from_u8(val).unwrap()
+    }
+}
+
+impl Heading for Direction {
     fn left(&self) -> Direction {
-        let val = ToPrimitive::to_u8(self).unwrap();
-        // println!("left {}", val);
-        FromPrimitive::from_u8(if val > 0 { val - 1 } else { 3 }).unwrap()
+        self.turn(6)
     }
     fn right(&self) -> Direction {
-        let val = (ToPrimitive::to_u8(self).unwrap() + 1) % 4;
-        // println!("right {}", val);
-        FromPrimitive::from_u8(val).unwrap()
+        self.turn(2)
+    }
+    fn half_left(&self) -> Option<Direction> {
+        Some(self.turn(7))
+    }
+    fn half_right(&self) -> Option<Direction> {
+        Some(self.turn(1))
+    }
+    fn back(&self) -> Direction {
+        self.turn(4)
     }
     fn vec(&self) -> (i64, i64) {
         match self {
             Direction::North => (1, 0),
+            Direction::NorthEast => (1, 1),
             Direction::East => (0, 1),
+            Direction::SouthEast => (-1, 1),
             Direction::South => (-1, 0),
+            Direction::SouthWest => (-1, -1),
             Direction::West => (0, -1),
+            Direction::NorthWest => (1, -1),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-enum Instr {
+/// Heading on a hex grid with flat-topped cells, listed clockwise in steps of 60 degrees
+///
+/// Positions on the hex grid are [axial coordinates](https://www.redblobgames.com/grids/hexagons/#coordinates-axial)
+/// `(q, r)`. Left and right turn by 60 degrees, half turns don't exist on this grid.
+#[derive(FromPrimitive, ToPrimitive, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexDirection {
+    North = 0,
+    NorthEast = 1,
+    SouthEast = 2,
+    South = 3,
+    SouthWest = 4,
+    NorthWest = 5,
+}
+
+impl HexDirection {
+    /// turns clockwise by `sixths` of a full turn
+    fn turn(&self, sixths: u8) -> HexDirection {
+        let val = (ToPrimitive::to_u8(self).unwrap() + sixths) % 6;
+        FromPrimitive::from_u8(val).unwrap()
+    }
+}
+
+impl Heading for HexDirection {
+    fn left(&self) -> HexDirection {
+        self.turn(5)
+    }
+    fn right(&self) -> HexDirection {
+        self.turn(1)
+    }
+    fn half_left(&self) -> Option<HexDirection> {
+        None
+    }
+    fn half_right(&self) -> Option<HexDirection> {
+        None
+    }
+    fn back(&self) -> HexDirection {
+        self.turn(3)
+    }
+    fn vec(&self) -> (i64, i64) {
+        match self {
+            HexDirection::North => (0, -1),
+            HexDirection::NorthEast => (1, -1),
+            HexDirection::SouthEast => (1, 0),
+            HexDirection::South => (0, 1),
+            HexDirection::SouthWest => (-1, 1),
+            HexDirection::NorthWest => (-1, 0),
+        }
+    }
+}
+
+/// A single instruction, written as its prefix followed by the amount of blocks to walk
+///
+/// Besides the puzzle's `L` and `R` this understands `HL`/`HR` (turn 45 degrees towards a
+/// diagonal), `F` (walk forward without turning) and `B` (U-turn, the amount is optional).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instr {
     Left(u64),
     Right(u64),
+    HalfLeft(u64),
+    HalfRight(u64),
+    Forward(u64),
+    Back(u64),
+}
+
+impl Instr {
+    /// turns from `direction` and returns the new direction with the amount of blocks to walk,
+    /// fails for half turns on a grid without diagonals
+    fn apply<D: Heading>(&self, direction: D) -> anyhow::Result<(D, u64)> {
+        let turned = match self {
+            Instr::Left(amount) => Some((direction.left(), *amount)),
+            Instr::Right(amount) => Some((direction.right(), *amount)),
+            Instr::HalfLeft(amount) => direction.half_left().map(|turned| (turned, *amount)),
+            Instr::HalfRight(amount) => direction.half_right().map(|turned| (turned, *amount)),
+            Instr::Forward(amount) => Some((direction, *amount)),
+            Instr::Back(amount) => Some((direction.back(), *amount)),
+        };
+        turned.ok_or_else(|| {
+            anyhow!(
+                "cannot follow {} facing {:?}, half turns are not supported on this grid",
+                self,
+                direction
+            )
+        })
+    }
+}
+
+impl FromStr for Instr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, amount) = s.split_at(s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len()));
+        let amount: Option<u64> = if amount.is_empty() {
+            None
+        } else {
+            Some(amount.parse()?)
+        };
+        Ok(match (prefix, amount) {
+            ("L", Some(amount)) => Instr::Left(amount),
+            ("R", Some(amount)) => Instr::Right(amount),
+            ("HL", Some(amount)) => Instr::HalfLeft(amount),
+            ("HR", Some(amount)) => Instr::HalfRight(amount),
+            ("F", Some(amount)) => Instr::Forward(amount),
+            ("B", amount) => Instr::Back(amount.unwrap_or(0)),
+            _ => bail!("invalid instruction {:?}", s),
+        })
+    }
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instr::Left(amount) => write!(f, "L{}", amount),
+            Instr::Right(amount) => write!(f, "R{}", amount),
+            Instr::HalfLeft(amount) => write!(f, "HL{}", amount),
+            Instr::HalfRight(amount) => write!(f, "HR{}", amount),
+            Instr::Forward(amount) => write!(f, "F{}", amount),
+            Instr::Back(0) => write!(f, "B"),
+            Instr::Back(amount) => write!(f, "B{}", amount),
+        }
+    }
 }
 
-fn execute1(instructions: &Vec<Instr>) -> (i64, i64) {
-    let mut pos = (0, 0);
-    let mut direction = Direction::North;
-    for instr in instructions {
-        let amount = match instr {
-            Instr::Left(amount) => {
-                direction = direction.left();
-                *amount
+/// formats instructions the way the puzzle input lists them, e.g. `R8, R4, R4, R8`
+pub fn format_instructions(instructions: &[Instr]) -> String {
+    instructions
+        .iter()
+        .map(|instr| instr.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Position and heading while following the instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Walker<D: Heading = Direction> {
+    pub pos: (i64, i64),
+    pub direction: D,
+}
+
+impl Default for Walker {
+    /// start at the given coordinates (where you just landed) and face North
+    fn default() -> Self {
+        Walker::new((0, 0), Direction::North)
+    }
+}
+
+impl<D: Heading> Walker<D> {
+    pub fn new(pos: (i64, i64), direction: D) -> Walker<D> {
+        Walker { pos, direction }
+    }
+
+    /// turns according to `instr` and walks all of its blocks at once
+    pub fn jump(&mut self, instr: &Instr) -> anyhow::Result<()> {
+        let (direction, amount) = instr.apply(self.direction)?;
+        self.direction = direction;
+        let vec = direction.vec();
+        self.pos.0 += vec.0 * amount as i64;
+        self.pos.1 += vec.1 * amount as i64;
+        Ok(())
+    }
+
+    /// turns according to `instr` and walks its blocks one at a time, yielding every position
+    pub fn follow<'a>(
+        &'a mut self,
+        instr: &Instr,
+    ) -> anyhow::Result<impl Iterator<Item = (i64, i64)> + 'a> {
+        let (direction, amount) = instr.apply(self.direction)?;
+        self.direction = direction;
+        let vec = direction.vec();
+        Ok((0..amount).map(move |_| {
+            self.pos.0 += vec.0;
+            self.pos.1 += vec.1;
+            self.pos
+        }))
+    }
+
+    /// position after following all instructions
+    pub fn execute1(mut self, instructions: &[Instr]) -> anyhow::Result<(i64, i64)> {
+        for instr in instructions {
+            self.jump(instr)?;
+        }
+        Ok(self.pos)
+    }
+
+    /// first position visited twice
+    pub fn execute2(mut self, instructions: &[Instr]) -> anyhow::Result<Option<(i64, i64)>> {
+        let mut history: HashSet<(i64, i64)> = HashSet::from([self.pos]);
+        for instr in instructions {
+            for pos in self.follow(instr)? {
+                if !history.insert(pos) {
+                    return Ok(Some(pos));
+                }
             }
-            Instr::Right(amount) => {
-                direction = direction.right();
-                *amount
+        }
+        Ok(None)
+    }
+
+    /// Part 1 under `metric`: how far is the destination from the start?
+    pub fn distance1(self, instructions: &[Instr], metric: Metric) -> anyhow::Result<f64> {
+        Ok(metric.distance(self.pos, self.execute1(instructions)?))
+    }
+
+    /// Part 2 under `metric`: how far is the first location visited twice from the start?
+    pub fn distance2(self, instructions: &[Instr], metric: Metric) -> anyhow::Result<Option<f64>> {
+        Ok(self
+            .execute2(instructions)?
+            .map(|revisit| metric.distance(self.pos, revisit)))
+    }
+}
+
+impl Walker {
+    /// Shortest list of `L`/`R` instructions which ends at `target`
+    ///
+    /// Every instruction turns and walks at least one block, like in the puzzle input. Fails
+    /// unless facing North, East, South or West.
+    pub fn instructions_to(&self, target: (i64, i64)) -> anyhow::Result<Vec<Instr>> {
+        let h = self.direction;
+        let forward = h.vec();
+        let right = h.right().vec();
+        if forward.0 != 0 && forward.1 != 0 {
+            bail!("cannot synthesize instructions facing {:?}", h);
+        }
+        let delta = (target.0 - self.pos.0, target.1 - self.pos.1);
+        // the first instruction always walks across the current heading
+        let across = delta.0 * right.0 + delta.1 * right.1;
+        let along = delta.0 * forward.0 + delta.1 * forward.1;
+        let across_dir = if across > 0 { h.right() } else { h.left() };
+        let along_dir = if along > 0 { h } else { h.back() };
+        let (across, along) = (across.unsigned_abs(), along.unsigned_abs());
+        Ok(match (across, along) {
+            (0, 0) => vec![],
+            (_, 0) => vec![turn_to(h, across_dir, across)?],
+            (0, _) => vec![
+                turn_to(h, h.right(), 1)?,
+                turn_to(h.right(), along_dir, along)?,
+                turn_to(along_dir, h.left(), 1)?,
+            ],
+            _ => vec![
+                turn_to(h, across_dir, across)?,
+                turn_to(across_dir, along_dir, along)?,
+            ],
+        })
+    }
+
+    /// Instructions which first visit `revisit` twice before ending at `target`, so the answer
+    /// of part 2 is `revisit` and the answer of part 1 is `target`, fails like
+    /// [`Walker::instructions_to`]
+    pub fn instructions_via(
+        &self,
+        revisit: (i64, i64),
+        target: (i64, i64),
+    ) -> anyhow::Result<Vec<Instr>> {
+        let to_revisit = self.instructions_to(revisit)?;
+        // walk a square ahead of `revisit` which ends on it again, on whichever side is
+        // still unvisited
+        let squares = [
+            [
+                Instr::Right(1),
+                Instr::Left(1),
+                Instr::Left(1),
+                Instr::Left(1),
+            ],
+            [
+                Instr::Left(1),
+                Instr::Right(1),
+                Instr::Right(1),
+                Instr::Right(1),
+            ],
+        ];
+        let mut instructions = None;
+        for square in squares {
+            let candidate = [to_revisit.clone(), square.to_vec()].concat();
+            if self.execute2(&candidate)? == Some(revisit) {
+                instructions = Some(candidate);
+                break;
             }
+        }
+        let Some(mut instructions) = instructions else {
+            bail!("no square around {:?} found", revisit);
         };
-        let vec = direction.vec();
-        pos.0 += vec.0 * amount as i64;
-        pos.1 += vec.1 * amount as i64;
+        let mut walker = *self;
+        for instr in &instructions {
+            walker.jump(instr)?;
+        }
+        instructions.extend(walker.instructions_to(target)?);
+        Ok(instructions)
+    }
+}
+
+/// the `L` or `R` instruction which turns from `from` to `to`
+fn turn_to(from: Direction, to: Direction, amount: u64) -> anyhow::Result<Instr> {
+    if from.left() == to {
+        Ok(Instr::Left(amount))
+    } else if from.right() == to {
+        Ok(Instr::Right(amount))
+    } else {
+        bail!("cannot turn from {:?} to {:?}", from, to)
     }
-    pos
 }
 
-fn execute2(instructions: &Vec<Instr>) -> Option<(i64, i64)> {
-    let mut pos = (0, 0);
-    let mut direction = Direction::North;
-    let mut history: Vec<(i64, i64)> = vec![pos];
-    for instr in instructions {
-        let amount = match instr {
-            Instr::Left(amount) => {
-                direction = direction.left();
-                *amount
+// every instruction is supported on the square grid, so these can't fail
+fn execute1(instructions: &[Instr]) -> (i64, i64) {
+    Walker::default().execute1(instructions).unwrap()
+}
+
+fn execute2(instructions: &[Instr]) -> Option<(i64, i64)> {
+    Walker::default().execute2(instructions).unwrap()
+}
+
+/// One instruction of a walk: the heading after turning and the blocks walked in a straight line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<D: Heading = Direction> {
+    pub from: (i64, i64),
+    pub to: (i64, i64),
+    pub direction: D,
+    pub blocks: u64,
+}
+
+/// An intersection visited more than once, with the step index of every visit
+///
+/// Step `0` is the starting position, every block walked increments the step by one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revisit {
+    pub pos: (i64, i64),
+    pub steps: Vec<usize>,
+}
+
+impl Revisit {
+    pub fn count(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+/// The complete walk of an instruction list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route<D: Heading = Direction> {
+    pub segments: Vec<Segment<D>>,
+    /// ordered by the step of the second visit, so the first entry is the answer of part 2
+    pub revisits: Vec<Revisit>,
+    /// minimum and maximum position reached, including the start
+    pub bounds: ((i64, i64), (i64, i64)),
+    /// first position with the largest manhattan distance from the start
+    pub farthest: (i64, i64),
+}
+
+impl<D: Heading> Walker<D> {
+    /// follows all instructions block by block and records the route
+    pub fn trace(mut self, instructions: &[Instr]) -> anyhow::Result<Route<D>> {
+        let mut segments = Vec::new();
+        let mut visits: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        visits.insert(self.pos, vec![0]);
+        let mut bounds = (self.pos, self.pos);
+        let start = self.pos;
+        let mut farthest = self.pos;
+        let mut step = 0;
+        for instr in instructions {
+            let from = self.pos;
+            let mut blocks = 0;
+            for pos in self.follow(instr)? {
+                blocks += 1;
+                step += 1;
+                visits.entry(pos).or_default().push(step);
+                bounds.0 = (bounds.0 .0.min(pos.0), bounds.0 .1.min(pos.1));
+                bounds.1 = (bounds.1 .0.max(pos.0), bounds.1 .1.max(pos.1));
+                let distance =
+                    |pos: (i64, i64)| manhattan_distance((pos.0 - start.0, pos.1 - start.1));
+                if distance(pos) > distance(farthest) {
+                    farthest = pos;
+                }
             }
-            Instr::Right(amount) => {
-                direction = direction.right();
-                *amount
+            segments.push(Segment {
+                from,
+                to: self.pos,
+                direction: self.direction,
+                blocks,
+            });
+        }
+        let mut revisits: Vec<Revisit> = visits
+            .into_iter()
+            .filter(|(_, steps)| steps.len() > 1)
+            .map(|(pos, steps)| Revisit { pos, steps })
+            .collect();
+        revisits.sort_unstable_by_key(|revisit| revisit.steps[1]);
+        Ok(Route {
+            segments,
+            revisits,
+            bounds,
+            farthest,
+        })
+    }
+}
+
+/// follows all instructions from the default start and records the route
+pub fn trace(instructions: &[Instr]) -> Route {
+    Walker::default().trace(instructions).unwrap()
+}
+
+/// City map with closed intersections (`#`), open intersections (`.`) and the start (`S`)
+///
+/// The first line is the northernmost row, intersections outside of the map are open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CityMap {
+    blocked: HashSet<(i64, i64)>,
+    /// minimum and maximum position covered by the map
+    bounds: ((i64, i64), (i64, i64)),
+}
+
+/// An instruction which walks through a closed intersection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    /// index of the instruction in the list
+    pub instr: usize,
+    pub pos: (i64, i64),
+}
+
+/// Result of following the instructions on a city map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CityWalk {
+    pub destination: (i64, i64),
+    pub collisions: Vec<Collision>,
+    /// length of the shortest path around closed intersections, `None` if there is none
+    pub shortest: Option<u64>,
+}
+
+impl FromStr for CityMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut start = None;
+        let mut blocked = Vec::new();
+        let mut width = 0;
+        let height = s.lines().count();
+        for (row, line) in s.lines().enumerate() {
+            width = width.max(line.len());
+            for (col, c) in line.chars().enumerate() {
+                match c {
+                    '#' => blocked.push((row as i64, col as i64)),
+                    '.' => {}
+                    'S' if start.is_none() => start = Some((row as i64, col as i64)),
+                    'S' => bail!("line {}: second start", row + 1),
+                    _ => bail!("line {}: invalid intersection {:?}", row + 1, c),
+                }
             }
+        }
+        let Some((start_row, start_col)) = start else {
+            bail!("map has no start");
         };
-        let vec = direction.vec();
-        for _ in 0..amount {
-            pos.0 += vec.0;
-            pos.1 += vec.1;
-            if history.contains(&pos) {
-                return Some(pos);
+        // rows count southwards, north counts upwards from the start
+        let pos = |(row, col): (i64, i64)| (start_row - row, col - start_col);
+        Ok(CityMap {
+            blocked: blocked.into_iter().map(pos).collect(),
+            bounds: (pos((height as i64 - 1, 0)), pos((0, width as i64 - 1))),
+        })
+    }
+}
+
+impl CityMap {
+    pub fn is_blocked(&self, pos: (i64, i64)) -> bool {
+        self.blocked.contains(&pos)
+    }
+
+    /// follows the instructions, reporting every closed intersection walked through, and
+    /// searches the shortest path from the start to the destination around them
+    pub fn walk(&self, mut walker: Walker, instructions: &[Instr]) -> CityWalk {
+        let start = walker.pos;
+        let mut collisions = Vec::new();
+        for (idx, instr) in instructions.iter().enumerate() {
+            for pos in walker.follow(instr).unwrap() {
+                if self.is_blocked(pos) {
+                    collisions.push(Collision { instr: idx, pos });
+                }
             }
-            history.push(pos);
         }
+        CityWalk {
+            destination: walker.pos,
+            collisions,
+            shortest: self.shortest_path(start, walker.pos),
+        }
+    }
+
+    /// breadth-first search around closed intersections
+    ///
+    /// The search is limited to the map and `from`/`to` with a ring of one block around it,
+    /// a shortest path never needs to leave that area.
+    pub fn shortest_path(&self, from: (i64, i64), to: (i64, i64)) -> Option<u64> {
+        if self.is_blocked(from) || self.is_blocked(to) {
+            return None;
+        }
+        let ((min_n, min_e), (max_n, max_e)) = self.bounds;
+        let min = (
+            min_n.min(from.0).min(to.0) - 1,
+            min_e.min(from.1).min(to.1) - 1,
+        );
+        let max = (
+            max_n.max(from.0).max(to.0) + 1,
+            max_e.max(from.1).max(to.1) + 1,
+        );
+        let mut seen = HashSet::from([from]);
+        let mut queue = VecDeque::from([(from, 0)]);
+        while let Some((pos, distance)) = queue.pop_front() {
+            if pos == to {
+                return Some(distance);
+            }
+            for direction in [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ] {
+                let vec = direction.vec();
+                let next = (pos.0 + vec.0, pos.1 + vec.1);
+                if next.0 < min.0 || next.1 < min.1 || next.0 > max.0 || next.1 > max.1 {
+                    continue;
+                }
+                if !self.is_blocked(next) && seen.insert(next) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// pixels per block in the rendered svg
+const SVG_SCALE: i64 = 10;
+/// pixels around the bounding box of the walk
+const SVG_MARGIN: i64 = 20;
+
+/// Renders the walk as SVG with gridlines, the walked path, the start, the final position and
+/// the first intersection visited twice
+///
+/// North is up and the image is scaled to the bounding box of the walk. The output only depends
+/// on the instructions, so it is suitable for snapshot tests.
+pub fn render_svg(instructions: &[Instr]) -> String {
+    let route = trace(instructions);
+    let end = execute1(instructions);
+    let revisit = execute2(instructions);
+    let ((min_n, min_e), (max_n, max_e)) = route.bounds;
+    let width = (max_e - min_e) * SVG_SCALE + 2 * SVG_MARGIN;
+    let height = (max_n - min_n) * SVG_SCALE + 2 * SVG_MARGIN;
+    let point = |pos: (i64, i64)| {
+        (
+            (pos.1 - min_e) * SVG_SCALE + SVG_MARGIN,
+            (max_n - pos.0) * SVG_SCALE + SVG_MARGIN,
+        )
+    };
+    // keep the amount of gridlines readable for large walks
+    let grid = (max_e - min_e).max(max_n - min_n) / 50 + 1;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+    );
+    svg += "<g stroke=\"#ddd\" stroke-width=\"1\">\n";
+    for e in (min_e..=max_e).filter(|e| e % grid == 0) {
+        let (x, _) = point((0, e));
+        svg += &format!(
+            "<line x1=\"{x}\" y1=\"{SVG_MARGIN}\" x2=\"{x}\" y2=\"{}\"/>\n",
+            height - SVG_MARGIN
+        );
+    }
+    for n in (min_n..=max_n).filter(|n| n % grid == 0) {
+        let (_, y) = point((n, 0));
+        svg += &format!(
+            "<line x1=\"{SVG_MARGIN}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\"/>\n",
+            width - SVG_MARGIN
+        );
+    }
+    svg += "</g>\n";
+
+    let mut points = vec![point((0, 0))];
+    points.extend(route.segments.iter().map(|segment| point(segment.to)));
+    let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+    svg += &format!(
+        "<polyline fill=\"none\" stroke=\"#000\" stroke-width=\"2\" points=\"{}\"/>\n",
+        points.join(" ")
+    );
+
+    let mut markers = vec![("start", "#0a0", (0, 0)), ("end", "#c00", end)];
+    if let Some(revisit) = revisit {
+        markers.push(("first revisit", "#f80", revisit));
+    }
+    for (title, color, pos) in markers {
+        let (x, y) = point(pos);
+        svg += &format!(
+            "<circle cx=\"{x}\" cy=\"{y}\" r=\"4\" fill=\"{color}\"><title>{title} {},{}</title></circle>\n",
+            pos.0, pos.1
+        );
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// How to measure the distance between two positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// blocks walked on the street grid
+    Manhattan,
+    /// moves of a king on a chess board, so diagonals count as one block
+    Chebyshev,
+    /// length of a straight line as the crow flies
+    Euclidean,
+    /// cells walked on a hex grid, expects axial coordinates as walked with [`HexDirection`]
+    Hex,
+}
+
+impl Metric {
+    /// exact distance in blocks, `None` for [`Metric::Euclidean`] which isn't a whole number
+    pub fn blocks(&self, a: (i64, i64), b: (i64, i64)) -> Option<u64> {
+        let (d0, d1) = (b.0 as i128 - a.0 as i128, b.1 as i128 - a.1 as i128);
+        match self {
+            Metric::Manhattan => Some((d0.unsigned_abs() + d1.unsigned_abs()) as u64),
+            // see https://en.wikipedia.org/wiki/Chebyshev_distance
+            Metric::Chebyshev => Some(d0.unsigned_abs().max(d1.unsigned_abs()) as u64),
+            Metric::Euclidean => None,
+            // see https://www.redblobgames.com/grids/hexagons/#distances-axial
+            Metric::Hex => Some(
+                ((d0.unsigned_abs() + d1.unsigned_abs() + (d0 + d1).unsigned_abs()) / 2) as u64,
+            ),
+        }
+    }
+
+    /// [`Metric::blocks`] as a float, the length of the straight line for
+    /// [`Metric::Euclidean`]
+    pub fn distance(&self, a: (i64, i64), b: (i64, i64)) -> f64 {
+        self.blocks(a, b).map_or_else(
+            || ((b.0 as f64) - (a.0 as f64)).hypot((b.1 as f64) - (a.1 as f64)),
+            |blocks| blocks as f64,
+        )
     }
-    None
 }
 
 // see https://en.wikipedia.org/wiki/Taxicab_geometry
 fn manhattan_distance(pos: (i64, i64)) -> u64 {
-    (pos.0.abs() + pos.1.abs()) as u64
+    pos.0.unsigned_abs() + pos.1.unsigned_abs()
 }
 
 #[cfg(test)]
@@ -194,4 +801,263 @@ mod tests {
         assert_eq!(target, (0, 4));
         assert_eq!(manhattan_distance(target), 4);
     }
+
+    #[test]
+    fn trace_examples() {
+        let route = trace(&parse_input("R8, R4, R4, R8"));
+        assert_eq!(route.segments.len(), 4);
+        assert_eq!(
+            route.segments[1],
+            Segment {
+                from: (0, 8),
+                to: (-4, 8),
+                direction: Direction::South,
+                blocks: 4,
+            }
+        );
+        assert_eq!(route.segments[3].to, (4, 4));
+        assert_eq!(route.revisits.len(), 1);
+        assert_eq!(
+            route.revisits[0].pos,
+            execute2(&parse_input("R8, R4, R4, R8")).unwrap()
+        );
+        assert_eq!(route.revisits[0].steps, vec![4, 20]);
+        assert_eq!(route.revisits[0].count(), 2);
+        assert_eq!(route.bounds, ((-4, 0), (4, 8)));
+        assert_eq!(route.farthest, (-4, 8));
+
+        let route = trace(&parse_input("R2, R2, R2, R2"));
+        assert_eq!(route.revisits[0].pos, (0, 0));
+        assert_eq!(route.revisits[0].steps, vec![0, 8]);
+    }
+
+    #[test]
+    fn extended_grammar() {
+        assert_eq!(
+            parse_input("R2, L3, F5, B, B4, HL2, HR1"),
+            vec![
+                Instr::Right(2),
+                Instr::Left(3),
+                Instr::Forward(5),
+                Instr::Back(0),
+                Instr::Back(4),
+                Instr::HalfLeft(2),
+                Instr::HalfRight(1),
+            ]
+        );
+        assert!("X5".parse::<Instr>().is_err());
+        assert!("L".parse::<Instr>().is_err());
+
+        // north 8 blocks, turn around and walk back 3
+        assert_eq!(execute1(&parse_input("F8, B3")), (5, 0));
+        // diagonals move along both axes
+        assert_eq!(execute1(&parse_input("HR2, HL3")), (5, 2));
+        assert_eq!(execute1(&parse_input("HL4, L1")), (3, -5));
+        assert_eq!(execute2(&parse_input("F3, B, F1")), Some((2, 0)));
+
+        let walker = Walker::new((10, -4), Direction::East);
+        assert_eq!(walker.execute1(&parse_input("R2, L3")).unwrap(), (8, -1));
+        assert_eq!(
+            walker.execute2(&parse_input("R8, R4, R4, R8")).unwrap(),
+            Some((6, -4))
+        );
+        assert_eq!(
+            walker.trace(&parse_input("F2")).unwrap().bounds,
+            ((10, -4), (10, -2))
+        );
+    }
+
+    #[test]
+    fn metrics() {
+        // exact beyond the 53 bits of a float and without overflowing
+        assert_eq!(part1(&parse_input("R9007199254740993")), 9007199254740993);
+        let far = parse_input("R4000000000, L4000000000");
+        assert_eq!(
+            Walker::default()
+                .distance1(&far, Metric::Euclidean)
+                .unwrap(),
+            4e9 * 2f64.sqrt()
+        );
+        assert_eq!(
+            Metric::Chebyshev.blocks((i64::MIN, 0), (i64::MAX, 0)),
+            Some(u64::MAX)
+        );
+        assert_eq!(Metric::Euclidean.blocks((0, 0), (3, 4)), None);
+
+        let walker = Walker::default();
+        let input = parse_input("R5, L5, R5, R3");
+        assert_eq!(walker.distance1(&input, Metric::Manhattan).unwrap(), 12.0);
+        assert_eq!(walker.distance1(&input, Metric::Chebyshev).unwrap(), 10.0);
+        assert_eq!(
+            walker.distance1(&input, Metric::Euclidean).unwrap(),
+            104f64.sqrt()
+        );
+        let input = parse_input("R8, R4, R4, R8");
+        assert_eq!(
+            walker.distance2(&input, Metric::Chebyshev).unwrap(),
+            Some(4.0)
+        );
+        assert_eq!(
+            walker
+                .distance2(&parse_input("R2"), Metric::Euclidean)
+                .unwrap(),
+            None
+        );
+
+        // walking the same instructions on a hex grid turns by 60 degrees
+        let hex = Walker::new((0, 0), HexDirection::North);
+        assert_eq!(hex.execute1(&parse_input("R2, L3")).unwrap(), (2, -5));
+        assert_eq!(
+            hex.distance1(&parse_input("R2, L3"), Metric::Hex).unwrap(),
+            5.0
+        );
+        assert_eq!(
+            hex.distance1(&parse_input("R3, R3, R3"), Metric::Hex)
+                .unwrap(),
+            6.0
+        );
+        // six right turns walk a full hexagon back to the start
+        let hexagon = parse_input("R1, R1, R1, R1, R1, R1");
+        assert_eq!(hex.execute1(&hexagon).unwrap(), (0, 0));
+        assert_eq!(hex.distance2(&hexagon, Metric::Hex).unwrap(), Some(0.0));
+        assert_eq!(Metric::Hex.blocks((0, 0), (2, -5)), Some(5));
+        assert_eq!(
+            hex.trace(&hexagon).unwrap().segments[2].direction,
+            HexDirection::South
+        );
+        // there is no heading between two hex directions
+        let error = hex.execute1(&parse_input("R1, HL2")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cannot follow HL2 facing NorthEast, half turns are not supported on this grid"
+        );
+        assert!(hex.execute2(&parse_input("HR1")).is_err());
+        assert!(hex.trace(&parse_input("HR1")).is_err());
+    }
+
+    #[test]
+    fn synthesize() {
+        let examples = "R8, R4, R4, R8, L2, F3, B, B4, HL2, HR1";
+        assert_eq!(format_instructions(&parse_input(examples)), examples);
+
+        for direction in [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ] {
+            let walker = Walker::new((2, -3), direction);
+            for target in [
+                (2, -3),
+                (5, -3),
+                (-7, -3),
+                (2, 4),
+                (2, -10),
+                (4, 1),
+                (-1, -8),
+            ] {
+                let instructions = walker.instructions_to(target).unwrap();
+                assert_eq!(walker.execute1(&instructions).unwrap(), target);
+                let vertical = matches!(direction, Direction::North | Direction::South);
+                let expected_len = match (target.0 == 2, target.1 == -3) {
+                    (true, true) => 0,
+                    (false, false) => 2,
+                    // one straight line is enough if it leads across the heading
+                    (same_north, _) if same_north == vertical => 1,
+                    _ => 3,
+                };
+                assert_eq!(instructions.len(), expected_len);
+
+                for revisit in [(2, -3), (3, -3), (2, -4), (6, 6), (-1, -3)] {
+                    let instructions = walker.instructions_via(revisit, target).unwrap();
+                    assert_eq!(walker.execute1(&instructions).unwrap(), target);
+                    assert_eq!(walker.execute2(&instructions).unwrap(), Some(revisit));
+                }
+            }
+        }
+
+        let instructions = Walker::default().instructions_via((0, 4), (3, 2)).unwrap();
+        let input = parse_input(&format_instructions(&instructions));
+        assert_eq!(part1(&input), 5);
+        assert_eq!(part2(&input), 4);
+
+        let diagonal = Walker::new((0, 0), Direction::NorthEast);
+        assert!(diagonal.instructions_to((3, 4)).is_err());
+        assert!(diagonal.instructions_via((1, 1), (3, 4)).is_err());
+    }
+
+    const CITY_MAP: &str = "......
+.#....
+.#S...
+.#....
+......";
+
+    #[test]
+    fn city_map() {
+        let map: CityMap = CITY_MAP.parse().unwrap();
+        assert!(map.is_blocked((1, -1)));
+        assert!(map.is_blocked((-1, -1)));
+        assert!(!map.is_blocked((0, 0)));
+
+        let walk = map.walk(Walker::default(), &parse_input("L2, R1"));
+        assert_eq!(walk.destination, (1, -2));
+        assert_eq!(
+            walk.collisions,
+            vec![Collision {
+                instr: 0,
+                pos: (0, -1)
+            }]
+        );
+        assert_eq!(walk.shortest, Some(5));
+
+        // without obstacles the shortest path is the manhattan distance
+        let walk = map.walk(Walker::default(), &parse_input("R5, L5, R5, R3"));
+        assert!(walk.collisions.is_empty());
+        assert_eq!(walk.shortest, Some(12));
+
+        // walls around the start leave no way out
+        let map: CityMap = ".#.\n#S#\n.#.".parse().unwrap();
+        assert_eq!(
+            map.walk(Walker::default(), &parse_input("R1, L1")).shortest,
+            None
+        );
+        assert_eq!(map.shortest_path((0, 0), (0, 0)), Some(0));
+
+        // an open destination inside a ring of walls can't be reached either
+        let map: CityMap = "S....\n..#..\n.#.#.\n..#..".parse().unwrap();
+        assert!(!map.is_blocked((-2, 2)));
+        let walk = map.walk(Walker::default(), &parse_input("R2, R2"));
+        assert_eq!(walk.destination, (-2, 2));
+        assert_eq!(walk.shortest, None);
+        assert_eq!(map.shortest_path((0, 0), (-3, 1)), Some(4));
+
+        assert!("..\n..".parse::<CityMap>().is_err());
+        assert!("S.\n.S".parse::<CityMap>().is_err());
+        assert!("S.\n.x".parse::<CityMap>().is_err());
+    }
+
+    #[test]
+    fn render_svg_examples() {
+        assert_eq!(
+            render_svg(&parse_input("R2, L3")),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="60" height="70" viewBox="0 0 60 70">
+<g stroke="#ddd" stroke-width="1">
+<line x1="20" y1="20" x2="20" y2="50"/>
+<line x1="30" y1="20" x2="30" y2="50"/>
+<line x1="40" y1="20" x2="40" y2="50"/>
+<line x1="20" y1="50" x2="40" y2="50"/>
+<line x1="20" y1="40" x2="40" y2="40"/>
+<line x1="20" y1="30" x2="40" y2="30"/>
+<line x1="20" y1="20" x2="40" y2="20"/>
+</g>
+<polyline fill="none" stroke="#000" stroke-width="2" points="20,50 40,50 40,20"/>
+<circle cx="20" cy="50" r="4" fill="#0a0"><title>start 0,0</title></circle>
+<circle cx="40" cy="20" r="4" fill="#c00"><title>end 3,2</title></circle>
+</svg>
+"##
+        );
+
+        let svg = render_svg(&parse_input("R8, R4, R4, R8"));
+        assert!(svg.contains(r##"<circle cx="60" cy="60" r="4" fill="#f80"><title>first revisit 0,4</title></circle>"##));
+    }
 }
@@ -183,7 +183,7 @@ impl Factory {
     }
 
     fn value_to_bot(&mut self, bot_nr: BotId, value: ChipId) {
-        let values = self.bots.entry(bot_nr).or_insert_with(Vec::new);
+        let values = self.bots.entry(bot_nr).or_default();
         values.push(value);
         values.sort_unstable();
         if values.len() == 2 {
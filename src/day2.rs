@@ -73,18 +73,23 @@
 //!
 //! Using the same instructions in your puzzle input, what is the correct bathroom code?
 
-const KEYPAD_1: &str = "123
+use crate::random::xorshift;
+use anyhow::{bail, Result};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+pub const KEYPAD_1: &str = "123
 456
 789";
 
-const KEYPAD_2: &str = "  1  
+pub const KEYPAD_2: &str = "  1  
  234 
 56789
  ABC 
   D  ";
 
 #[aoc_generator(day2)]
-fn parse_input(input: &str) -> Vec<Vec<Instr>> {
+pub fn parse_input(input: &str) -> Vec<Vec<Instr>> {
     input
         .lines()
         .map(|line| {
@@ -94,6 +99,10 @@ fn parse_input(input: &str) -> Vec<Vec<Instr>> {
                     'R' => Instr::Right,
                     'U' => Instr::Up,
                     'D' => Instr::Down,
+                    'Q' => Instr::UpLeft,
+                    'E' => Instr::UpRight,
+                    'Z' => Instr::DownLeft,
+                    'C' => Instr::DownRight,
                     _ => panic!("invalid input"),
                 })
                 .collect()
@@ -103,22 +112,75 @@ fn parse_input(input: &str) -> Vec<Vec<Instr>> {
 
 /// Part 1: What is the bathroom code?
 #[aoc(day2, part1)]
-fn part1(input: &Vec<Vec<Instr>>) -> String {
-    execute(input, KEYPAD_1, '5')
+fn part1(input: &[Vec<Instr>]) -> String {
+    Keypad::new(KEYPAD_1, '5')
+        .unwrap()
+        .transitions()
+        .execute(input)
 }
 
 /// Part 2: Using the same instructions in your puzzle input, what is the correct bathroom code?
 #[aoc(day2, part2)]
-fn part2(input: &Vec<Vec<Instr>>) -> String {
-    execute(input, KEYPAD_2, '5')
+fn part2(input: &[Vec<Instr>]) -> String {
+    Keypad::new(KEYPAD_2, '5')
+        .unwrap()
+        .transitions()
+        .execute(input)
+}
+
+/// A single move, the diagonal moves `Q`, `E`, `Z` and `C` need a keypad with
+/// [`Topology::diagonals`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    Up = 0,
+    Down = 1,
+    Left = 2,
+    Right = 3,
+    UpLeft = 4,
+    UpRight = 5,
+    DownLeft = 6,
+    DownRight = 7,
+}
+
+/// all moves, the orthogonal ones first
+const INSTRS: [Instr; 8] = [
+    Instr::Up,
+    Instr::Down,
+    Instr::Left,
+    Instr::Right,
+    Instr::UpLeft,
+    Instr::UpRight,
+    Instr::DownLeft,
+    Instr::DownRight,
+];
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Instr::Up => 'U',
+            Instr::Down => 'D',
+            Instr::Left => 'L',
+            Instr::Right => 'R',
+            Instr::UpLeft => 'Q',
+            Instr::UpRight => 'E',
+            Instr::DownLeft => 'Z',
+            Instr::DownRight => 'C',
+        };
+        write!(f, "{}", c)
+    }
 }
 
-#[derive(Debug, Clone)]
-enum Instr {
-    Up,
-    Down,
-    Left,
-    Right,
+/// formats instruction lines the way the puzzle input lists them
+pub fn format_instructions(input: &[Vec<Instr>]) -> String {
+    input
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|instr| instr.to_string())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 impl Instr {
@@ -128,46 +190,437 @@ impl Instr {
             Instr::Down => (1, 0),
             Instr::Left => (0, -1),
             Instr::Right => (0, 1),
+            Instr::UpLeft => (-1, -1),
+            Instr::UpRight => (-1, 1),
+            Instr::DownLeft => (1, -1),
+            Instr::DownRight => (1, 1),
+        }
+    }
+}
+
+/// What happens to a move which doesn't lead to a button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Edge {
+    /// the move is ignored, like in the puzzle
+    #[default]
+    Sticky,
+    /// the move continues over gaps and wraps around to the opposite side of the layout until it
+    /// reaches a button, on a full rectangular keypad this is a torus
+    Wrap,
+}
+
+/// How buttons of a keypad are connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Topology {
+    pub edge: Edge,
+    /// allow the diagonal moves `Q`, `E`, `Z` and `C`, otherwise they are ignored
+    pub diagonals: bool,
+}
+
+/// Keypad layout where every character except space is a button
+///
+/// Spaces are gaps without a button and shorter lines are padded with gaps, so the layout is
+/// always rectangular.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keypad {
+    rows: Vec<Vec<Option<char>>>,
+    start: (i64, i64),
+    topology: Topology,
+}
+
+impl Keypad {
+    /// parses and validates `layout`, the instructions start on the `start` button
+    pub fn new(layout: &str, start: char) -> Result<Keypad> {
+        let lines: Vec<&str> = layout.trim_end_matches(['\r', '\n']).lines().collect();
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut positions: HashMap<char, (usize, usize)> = HashMap::new();
+        let mut rows = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            let mut keys = vec![None; width];
+            for (col, key) in line.chars().enumerate() {
+                if key == ' ' {
+                    continue;
+                }
+                if key.is_whitespace() || key.is_control() {
+                    bail!("line {} column {}: invalid key {:?}", row + 1, col + 1, key);
+                }
+                if let Some((other_row, other_col)) = positions.insert(key, (row, col)) {
+                    bail!(
+                        "line {} column {}: key {:?} already used at line {} column {}",
+                        row + 1,
+                        col + 1,
+                        key,
+                        other_row + 1,
+                        other_col + 1
+                    );
+                }
+                keys[col] = Some(key);
+            }
+            rows.push(keys);
+        }
+        if positions.is_empty() {
+            bail!("keypad has no keys");
+        }
+        let Some(&(row, col)) = positions.get(&start) else {
+            bail!("start key {:?} not found on keypad", start);
+        };
+        Ok(Keypad {
+            rows,
+            start: (row as i64, col as i64),
+            topology: Topology::default(),
+        })
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Keypad {
+        self.topology = topology;
+        self
+    }
+
+    /// position after moving from `pos`, which stays on `pos` if the move isn't possible
+    fn step(&self, pos: (i64, i64), instr: Instr) -> (i64, i64) {
+        if instr as usize >= self.moves() {
+            return pos;
+        }
+        let vec = instr.vec();
+        let target = (pos.0 + vec.0, pos.1 + vec.1);
+        match self.topology.edge {
+            Edge::Sticky if self.get(target).is_some() => target,
+            Edge::Sticky => pos,
+            Edge::Wrap => {
+                let (height, width) = (self.rows.len() as i64, self.rows[0].len() as i64);
+                // terminates at the latest when arriving on `pos` again
+                let mut target = target;
+                loop {
+                    target = (target.0.rem_euclid(height), target.1.rem_euclid(width));
+                    if self.get(target).is_some() {
+                        return target;
+                    }
+                    target = (target.0 + vec.0, target.1 + vec.1);
+                }
+            }
+        }
+    }
+
+    /// amount of moves of [`INSTRS`] which are allowed
+    fn moves(&self) -> usize {
+        if self.topology.diagonals {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// the key at `pos`, `None` if there is no button
+    fn get(&self, pos: (i64, i64)) -> Option<char> {
+        if pos.0 < 0 || pos.1 < 0 {
+            return None;
+        }
+        *self.rows.get(pos.0 as usize)?.get(pos.1 as usize)?
+    }
+}
+
+/// Keypad compiled into a dense table of the key reached from every key in every direction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transitions {
+    keys: Vec<char>,
+    /// indexed by key and then by `Instr as usize`
+    next: Vec<[usize; 8]>,
+    start: usize,
+    /// amount of moves of [`INSTRS`] which are allowed
+    moves: usize,
+}
+
+impl Keypad {
+    pub fn transitions(&self) -> Transitions {
+        let mut positions = Vec::new();
+        for (row, keys) in self.rows.iter().enumerate() {
+            for (col, key) in keys.iter().enumerate() {
+                if key.is_some() {
+                    positions.push((row as i64, col as i64));
+                }
+            }
+        }
+        let index: HashMap<(i64, i64), usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(idx, &pos)| (pos, idx))
+            .collect();
+        let next = positions
+            .iter()
+            .map(|&pos| INSTRS.map(|instr| index[&self.step(pos, instr)]))
+            .collect();
+        Transitions {
+            keys: positions
+                .iter()
+                .map(|&pos| self.get(pos).unwrap())
+                .collect(),
+            next,
+            start: index[&self.start],
+            moves: self.moves(),
         }
     }
 }
 
-fn keypad_get(keypad: &str, pos: (i64, i64)) -> Option<char> {
-    if pos.0 < 0 || pos.1 < 0 {
-        return None;
+impl Transitions {
+    /// same as [`execute`], but every move is a single table lookup
+    pub fn execute(&self, input: &[Vec<Instr>]) -> String {
+        let mut key = self.start;
+        input
+            .iter()
+            .map(|line| {
+                for &instr in line {
+                    key = self.next[key][instr as usize];
+                }
+                self.keys[key]
+            })
+            .collect()
+    }
+}
+
+impl Transitions {
+    /// Shortest instruction lines which type `code`, starting on the start key
+    ///
+    /// Pressing the same key twice in a row wastes a move into a wall, or steps away and back
+    /// if there is no wall next to it. An empty line would get lost when the input is trimmed.
+    pub fn instructions_for(&self, code: &str) -> Result<Vec<Vec<Instr>>> {
+        let mut key = self.start;
+        code.chars()
+            .map(|c| {
+                let Some(target) = self.keys.iter().position(|&other| other == c) else {
+                    bail!("key {:?} not found on keypad", c);
+                };
+                let line = if target == key {
+                    self.stay_on(key)
+                } else {
+                    let Some(line) = self.shortest_path(key, target) else {
+                        bail!("key {:?} can't be reached from {:?}", c, self.keys[key]);
+                    };
+                    line
+                };
+                key = target;
+                Ok(line)
+            })
+            .collect()
+    }
+
+    /// Like [`Transitions::instructions_for`], with `noise` wasted moves into walls added to
+    /// every line which still type the same code
+    ///
+    /// The same `seed` always generates the same lines. Keys without a wall next to them
+    /// never get noise, so lines may contain less than `noise` wasted moves.
+    pub fn noisy_instructions_for(
+        &self,
+        code: &str,
+        noise: usize,
+        seed: u64,
+    ) -> Result<Vec<Vec<Instr>>> {
+        let mut next = xorshift(seed);
+        let mut random = move |max: usize| next() as usize % max;
+        let mut key = self.start;
+        let mut lines = self.instructions_for(code)?;
+        for line in lines.iter_mut() {
+            for _ in 0..noise {
+                let at = random(line.len() + 1);
+                let current = line[..at]
+                    .iter()
+                    .fold(key, |key, &instr| self.next[key][instr as usize]);
+                let walls: Vec<Instr> = INSTRS[..self.moves]
+                    .iter()
+                    .copied()
+                    .filter(|&instr| self.next[current][instr as usize] == current)
+                    .collect();
+                if !walls.is_empty() {
+                    line.insert(at, walls[random(walls.len())]);
+                }
+            }
+            key = line
+                .iter()
+                .fold(key, |key, &instr| self.next[key][instr as usize]);
+        }
+        Ok(lines)
     }
-    let line = keypad.lines().nth(pos.0 as usize)?;
-    let key = line.chars().nth(pos.1 as usize)?;
-    if key != ' ' {
-        Some(key)
-    } else {
+
+    /// shortest non-empty line which ends on `key` again
+    fn stay_on(&self, key: usize) -> Vec<Instr> {
+        let moves = &INSTRS[..self.moves];
+        if let Some(&wall) = moves
+            .iter()
+            .find(|&&instr| self.next[key][instr as usize] == key)
+        {
+            return vec![wall];
+        }
+        moves
+            .iter()
+            .filter_map(|&instr| {
+                let back = self.shortest_path(self.next[key][instr as usize], key)?;
+                Some([vec![instr], back].concat())
+            })
+            .min_by_key(|line| line.len())
+            .unwrap_or_default()
+    }
+
+    /// breadth-first search of the moves from key `from` to key `to`
+    fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<Instr>> {
+        let mut previous: Vec<Option<(usize, Instr)>> = vec![None; self.keys.len()];
+        let mut queue = VecDeque::from([from]);
+        while let Some(key) = queue.pop_front() {
+            if key == to {
+                let mut path = Vec::new();
+                let mut key = to;
+                while key != from {
+                    let (before, instr) = previous[key].unwrap();
+                    path.push(instr);
+                    key = before;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &instr in &INSTRS[..self.moves] {
+                let next = self.next[key][instr as usize];
+                if next != from && previous[next].is_none() {
+                    previous[next] = Some((key, instr));
+                    queue.push_back(next);
+                }
+            }
+        }
         None
     }
 }
 
-fn execute(input: &Vec<Vec<Instr>>, keypad: &str, start: char) -> String {
+pub fn execute(input: &[Vec<Instr>], keypad: &Keypad) -> String {
+    walk(input, keypad, |_| {})
+}
+
+/// A single move of a [`Trace`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    /// index of the instruction line
+    pub line: usize,
+    pub from: char,
+    pub instr: Instr,
+    pub to: char,
+    /// the move didn't lead to another button and was wasted
+    pub blocked: bool,
+}
+
+/// Every move made while typing a code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub code: String,
+    pub start: char,
+    pub moves: Vec<Move>,
+}
+
+impl Trace {
+    /// how often every key was visited: the start and every move onto it, wasted moves don't
+    /// count, see [`Trace::wasted`]
+    pub fn visits(&self) -> HashMap<char, usize> {
+        let mut visits = HashMap::from([(self.start, 1)]);
+        for m in self.moves.iter().filter(|m| !m.blocked) {
+            *visits.entry(m.to).or_default() += 1;
+        }
+        visits
+    }
+
+    /// how many moves were wasted hitting an edge, per key
+    pub fn wasted(&self) -> HashMap<char, usize> {
+        let mut wasted = HashMap::new();
+        for m in self.moves.iter().filter(|m| m.blocked) {
+            *wasted.entry(m.from).or_default() += 1;
+        }
+        wasted
+    }
+}
+
+/// same as [`execute`], but records every move
+pub fn execute_traced(input: &[Vec<Instr>], keypad: &Keypad) -> Trace {
+    let mut moves = Vec::new();
+    let code = walk(input, keypad, |m| moves.push(m));
+    Trace {
+        code,
+        start: keypad.get(keypad.start).unwrap(),
+        moves,
+    }
+}
+
+/// follows the instructions on the keypad and calls `on_move` for every move
+fn walk(input: &[Vec<Instr>], keypad: &Keypad, mut on_move: impl FnMut(Move)) -> String {
     let mut code = String::new();
-    let mut pos = (0, 0);
-    for (row, line) in keypad.lines().enumerate() {
-        if let Some(col) = line.find(start) {
-            pos.0 = row as i64;
-            pos.1 = col as i64;
-            break;
-        }
-    }
-    for line in input {
-        for instr in line {
-            let vec = instr.vec();
-            let target = (pos.0 + vec.0, pos.1 + vec.1);
-            if keypad_get(keypad, target).is_some() {
-                pos = target;
-            }
+    let mut pos = keypad.start;
+    for (idx, line) in input.iter().enumerate() {
+        for &instr in line {
+            let target = keypad.step(pos, instr);
+            on_move(Move {
+                line: idx,
+                from: keypad.get(pos).unwrap(),
+                instr,
+                to: keypad.get(target).unwrap(),
+                blocked: target == pos,
+            });
+            pos = target;
         }
-        code += &keypad_get(keypad, pos).unwrap().to_string();
+        code += &keypad.get(pos).unwrap().to_string();
     }
     code
 }
 
+impl Keypad {
+    /// Renders the keypad with visits and wasted moves of every key as `key visits/wasted`,
+    /// followed by a summary which explains that notation
+    pub fn heatmap(&self, trace: &Trace) -> String {
+        let visits = trace.visits();
+        let wasted = trace.wasted();
+        let cells: Vec<Vec<Option<String>>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|key| {
+                        key.map(|key| {
+                            format!(
+                                "{} {}/{}",
+                                key,
+                                visits.get(&key).unwrap_or(&0),
+                                wasted.get(&key).unwrap_or(&0)
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = cells
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|cell| cell.len())
+            .max();
+        let width = width.unwrap_or(0);
+        let mut out = String::new();
+        for row in cells {
+            let line: Vec<String> = row
+                .iter()
+                .map(|cell| match cell {
+                    Some(cell) => format!("[{:<width$}]", cell),
+                    None => " ".repeat(width + 2),
+                })
+                .collect();
+            out += line.join(" ").trim_end();
+            out += "\n";
+        }
+        let blocked = trace.moves.iter().filter(|m| m.blocked).count();
+        out += &format!(
+            "{} moves, {} wasted, keys show `key visits/wasted`\n",
+            trace.moves.len(),
+            blocked
+        );
+        out
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,13 +634,195 @@ UUUUD";
     fn part1_examples() {
         let input = parse_input(EXAMPLE);
         // So, in this example, the bathroom code is `1985`.
-        assert_eq!(execute(&input, KEYPAD_1, '5'), "1985");
+        assert_eq!(
+            execute(&input, &Keypad::new(KEYPAD_1, '5').unwrap()),
+            "1985"
+        );
     }
 
     #[test]
     fn part2_examples() {
         let input = parse_input(EXAMPLE);
         // So, given the actual keypad layout, the code would be `5DB3`.
-        assert_eq!(execute(&input, KEYPAD_2, '5'), "5DB3");
+        assert_eq!(
+            execute(&input, &Keypad::new(KEYPAD_2, '5').unwrap()),
+            "5DB3"
+        );
+    }
+
+    #[test]
+    fn keypad_validation() {
+        let keypad = Keypad::new(KEYPAD_2, 'D').unwrap();
+        assert_eq!(keypad.start, (4, 2));
+        assert_eq!(keypad.get((0, 2)), Some('1'));
+        assert_eq!(keypad.get((0, 1)), None);
+        assert_eq!(keypad.get((5, 2)), None);
+        // shorter lines are padded with gaps
+        assert_eq!(
+            Keypad::new("  1\n 234\n56789\n", '5').unwrap().rows[0],
+            vec![None, None, Some('1'), None, None]
+        );
+
+        let err = |layout, start| Keypad::new(layout, start).unwrap_err().to_string();
+        assert_eq!(
+            err("123\n456\n729", '5'),
+            "line 3 column 2: key '2' already used at line 1 column 2"
+        );
+        assert_eq!(err("123\n456", '9'), "start key '9' not found on keypad");
+        assert_eq!(err("1\t3", '1'), "line 1 column 2: invalid key '\\t'");
+        assert_eq!(err("", '5'), "keypad has no keys");
+    }
+
+    #[test]
+    fn transitions() {
+        let input = parse_input(EXAMPLE);
+        let table = Keypad::new(KEYPAD_1, '5').unwrap().transitions();
+        assert_eq!(table.keys, "123456789".chars().collect::<Vec<_>>());
+        // from `1` up and left stay on `1`
+        assert_eq!(table.next[0], [0, 3, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(table.execute(&input), "1985");
+
+        let keypad = Keypad::new(KEYPAD_2, '5').unwrap();
+        assert_eq!(keypad.transitions().execute(&input), "5DB3");
+        assert_eq!(
+            keypad
+                .transitions()
+                .execute(&parse_input(include_str!("../input/2016/day2.txt"))),
+            execute(
+                &parse_input(include_str!("../input/2016/day2.txt")),
+                &keypad
+            )
+        );
+    }
+
+    #[test]
+    fn instructions_for_code() {
+        let table = Keypad::new(KEYPAD_2, '5').unwrap().transitions();
+        let lines = table.instructions_for("5DB3").unwrap();
+        assert_eq!(format_instructions(&lines), "U\nRDRD\nU\nUU");
+        // fixtures are read back trimmed, so repeated keys must not produce empty lines
+        let fixture = format_instructions(&lines);
+        assert_eq!(table.execute(&parse_input(fixture.trim())), "5DB3");
+        let lines = table.instructions_for("55D11").unwrap();
+        let fixture = format_instructions(&lines);
+        assert_eq!(table.execute(&parse_input(fixture.trim())), "55D11");
+
+        let table = Keypad::new(KEYPAD_1, '5').unwrap().transitions();
+        let lines = table.instructions_for("1985").unwrap();
+        assert_eq!(format_instructions(&lines), "UL\nDDRR\nL\nU");
+        assert_eq!(
+            table.execute(&parse_input(&format_instructions(&lines))),
+            "1985"
+        );
+
+        for seed in 1..20 {
+            let lines = table.noisy_instructions_for("1985", 5, seed).unwrap();
+            assert_eq!(table.execute(&lines), "1985");
+            assert!(lines.iter().map(|line| line.len()).sum::<usize>() > 6);
+        }
+        assert_eq!(
+            table.noisy_instructions_for("1985", 3, 42).unwrap(),
+            table.noisy_instructions_for("1985", 3, 42).unwrap()
+        );
+
+        assert!(table.instructions_for("12X").is_err());
+        let split = Keypad::new("1 2", '1').unwrap().transitions();
+        assert!(split.instructions_for("2").is_err());
+    }
+
+    #[test]
+    fn topologies() {
+        let input = parse_input(EXAMPLE);
+        for (layout, code) in [(KEYPAD_1, "1985"), (KEYPAD_2, "5DB3")] {
+            let keypad = Keypad::new(layout, '5').unwrap();
+            assert_eq!(keypad.clone().with_topology(Topology::default()), keypad);
+            assert_eq!(execute(&input, &keypad), code);
+            // the example doesn't contain diagonal moves
+            let keypad = keypad.with_topology(Topology {
+                edge: Edge::Sticky,
+                diagonals: true,
+            });
+            assert_eq!(keypad.transitions().execute(&input), code);
+        }
+
+        let torus = Topology {
+            edge: Edge::Wrap,
+            diagonals: false,
+        };
+        let keypad = Keypad::new(KEYPAD_1, '5').unwrap().with_topology(torus);
+        // `ULL` wraps from `1` to `3`, `RRDDD` wraps from `3` to `1` and from `8` to `2`
+        assert_eq!(execute(&input, &keypad), "3211");
+        assert_eq!(keypad.transitions().execute(&input), "3211");
+        // without walls a repeated key steps away and back
+        let lines = keypad.transitions().instructions_for("55").unwrap();
+        assert_eq!(format_instructions(&lines), "UD\nUD");
+
+        let keypad = Keypad::new(KEYPAD_2, '5').unwrap().with_topology(torus);
+        // left of `5` wraps to `9`, up from `9` stays on `9` as its column has no other key
+        assert_eq!(execute(&parse_input("L\nU\nRRRRRR"), &keypad), "995");
+        assert_eq!(keypad.transitions().execute(&parse_input("UUUUU")), "5");
+
+        let diagonal = Topology {
+            edge: Edge::Sticky,
+            diagonals: true,
+        };
+        let input = parse_input("Q\nCC\nQE\nZZ");
+        let keypad = Keypad::new(KEYPAD_1, '5').unwrap();
+        assert_eq!(execute(&input, &keypad), "5555");
+        let keypad = keypad.with_topology(diagonal);
+        assert_eq!(execute(&input, &keypad), "1937");
+        assert_eq!(keypad.transitions().execute(&input), "1937");
+        assert_eq!(
+            format_instructions(&keypad.transitions().instructions_for("973").unwrap()),
+            "C\nLL\nEE"
+        );
+
+        let keypad = Keypad::new(KEYPAD_2, '5').unwrap().with_topology(diagonal);
+        assert_eq!(execute(&parse_input("E\nE\nC\nCC"), &keypad), "2149");
+    }
+
+    #[test]
+    fn trace_and_heatmap() {
+        let input = parse_input(EXAMPLE);
+        let keypad = Keypad::new(KEYPAD_1, '5').unwrap();
+        let trace = execute_traced(&input, &keypad);
+        assert_eq!(trace.code, "1985");
+        assert_eq!(trace.moves.len(), 18);
+        assert_eq!(
+            trace.moves[2],
+            Move {
+                line: 0,
+                from: '1',
+                instr: Instr::Left,
+                to: '1',
+                blocked: true,
+            }
+        );
+        assert_eq!(trace.moves.iter().filter(|m| m.blocked).count(), 4);
+        assert_eq!(
+            keypad.heatmap(&trace),
+            "[1 1/1] [2 3/2] [3 1/0]
+[4 0/0] [5 4/0] [6 2/0]
+[7 0/0] [8 2/0] [9 2/1]
+18 moves, 4 wasted, keys show `key visits/wasted`
+"
+        );
+
+        let keypad = Keypad::new(KEYPAD_2, '5').unwrap();
+        let trace = execute_traced(&input, &keypad);
+        assert_eq!(trace.code, "5DB3");
+        // bumping into an edge is a wasted move, not another visit
+        assert_eq!(trace.visits()[&'5'], 1);
+        assert_eq!(trace.wasted()[&'5'], 3);
+        assert_eq!(
+            keypad.heatmap(&trace),
+            "                [1 1/1]
+        [2 0/0] [3 2/0] [4 0/0]
+[5 1/3] [6 1/0] [7 2/0] [8 0/0] [9 0/0]
+        [A 0/0] [B 3/0] [C 1/1]
+                [D 1/2]
+18 moves, 7 wasted, keys show `key visits/wasted`
+"
+        );
     }
 }
@@ -35,70 +35,362 @@
 //! **In your puzzle input, and instead reading by columns, how many of the listed triangles
 //! are possible?**
 
+use anyhow::{bail, Context, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+
 #[aoc_generator(day3)]
-fn parse_input(input: &str) -> Vec<[u64; 3]> {
+fn parse_input(input: &str) -> Matrix {
+    parse_matrix(input).unwrap()
+}
+
+/// Parses three side lengths per line, separated by any whitespace
+///
+/// Blank lines are skipped, malformed lines are reported with their line number.
+pub fn parse_triangles(input: &str) -> Result<Vec<[u64; 3]>> {
+    Ok(parse_rows(input, Some(3))?
+        .into_iter()
+        .map(|row| [row[0], row[1], row[2]])
+        .collect())
+}
+
+/// Parses rows of numbers separated by any whitespace, every row needs as many numbers as the
+/// first one
+pub fn parse_matrix(input: &str) -> Result<Matrix> {
+    let rows = parse_rows(input, None)?;
+    let width = rows.first().map_or(0, |row| row.len());
+    Ok(Matrix { rows, width })
+}
+
+/// parses rows of `width` numbers, or as many as the first row has
+fn parse_rows(input: &str, mut width: Option<usize>) -> Result<Vec<Vec<u64>>> {
     input
         .lines()
-        .map(|line| {
-            let mut triangle = [0u64; 3];
-            let parts: Vec<u64> = line
-                .trim()
-                .replace("   ", " ")
-                .replace("  ", " ")
-                .split(' ')
-                .map(|c| c.parse().unwrap())
-                .collect();
-            triangle[0] = parts[0];
-            triangle[1] = parts[1];
-            triangle[2] = parts[2];
-            triangle
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            let parts = parse_row(idx, line, width)?;
+            width = Some(parts.len());
+            Ok(parts)
         })
         .collect()
 }
 
+/// parses the line with index `idx`, which needs `width` numbers if given
+fn parse_row(idx: usize, line: &str, width: Option<usize>) -> Result<Vec<u64>> {
+    let parts = line
+        .split_whitespace()
+        .map(|part| {
+            part.parse()
+                .with_context(|| format!("line {}: invalid number {:?}", idx + 1, part))
+        })
+        .collect::<Result<Vec<u64>>>()?;
+    match width {
+        Some(width) if parts.len() != width => bail!(
+            "line {}: expected {} numbers, found {}",
+            idx + 1,
+            width,
+            parts.len()
+        ),
+        _ => Ok(parts),
+    }
+}
+
+/// Possible triangles of part 1 and part 2, see [`count_streaming`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counts {
+    pub rows: usize,
+    /// possible triangles reading by rows like in part 1
+    pub by_rows: usize,
+    /// possible triangles reading by columns like in part 2
+    pub by_columns: usize,
+}
+
+/// Counts possible triangles of both parts in a single pass over `reader`
+///
+/// Only the current line and a window of the last three rows are kept in memory, so inputs of
+/// any size can be counted.
+pub fn count_streaming(mut reader: impl BufRead) -> Result<Counts> {
+    let mut counts = Counts::default();
+    let mut window = [[0u64; 3]; 3];
+    let mut line = String::new();
+    let mut idx = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        if !line.trim().is_empty() {
+            let row = parse_row(idx, &line, Some(3))?;
+            let row = [row[0], row[1], row[2]];
+            if valid_triange(&row) {
+                counts.by_rows += 1;
+            }
+            window[counts.rows % 3] = row;
+            counts.rows += 1;
+            if counts.rows.is_multiple_of(3) {
+                counts.by_columns += (0..3)
+                    .filter(|&col| valid_triange(&window.map(|row| row[col])))
+                    .count();
+            }
+        }
+        idx += 1;
+    }
+    if !counts.rows.is_multiple_of(3) {
+        bail!(
+            "reading by {:?} needs a multiple of 3 rows, found {} rows",
+            Layout::Columns,
+            counts.rows
+        );
+    }
+    Ok(counts)
+}
+
 /// Part 1: In your puzzle input, how many of the listed triangles are possible?
 #[aoc(day3, part1)]
-fn part1(input: &[[u64; 3]]) -> usize {
-    input
-        .iter()
-        .filter(|triangle| valid_triange(triangle))
-        .count()
+fn part1(input: &Matrix) -> usize {
+    count_possible(input, 3, Layout::Rows).unwrap()
 }
 
 /// Part 2: In your puzzle input, and instead reading by columns, how many of the listed triangles
 /// are possible?
 #[aoc(day3, part2)]
-fn part2(input: &[[u64; 3]]) -> usize {
-    rebuild_triangles(input)
+fn part2(input: &Matrix) -> usize {
+    count_possible(input, 3, Layout::Columns).unwrap()
+}
+
+/// how many of the `sides`-sided polygons read from `matrix` are possible, fails like
+/// [`Matrix::shapes`]
+pub fn count_possible(matrix: &Matrix, sides: usize, layout: Layout) -> Result<usize> {
+    Ok(matrix
+        .shapes(sides, layout)?
         .iter()
-        .filter(|triangle| valid_triange(triangle))
-        .count()
-}
-
-// interpret triangles row-wise
-#[allow(clippy::identity_op)]
-fn rebuild_triangles(input: &[[u64; 3]]) -> Vec<[u64; 3]> {
-    let mut triangles = Vec::new();
-
-    for col in 0..3 {
-        for row_block in 0..input.len() / 3 {
-            let row = row_block * 3;
-            let mut triangle = [0u64; 3];
-            triangle[0] = input[row + 0][col];
-            triangle[1] = input[row + 1][col];
-            triangle[2] = input[row + 2][col];
-            triangles.push(triangle);
+        .filter(|shape| valid_polygon(shape))
+        .count())
+}
+
+/// Rows of numbers which all have the same amount of columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    rows: Vec<Vec<u64>>,
+    width: usize,
+}
+
+/// Where to find the sides of a shape in a [`Matrix`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// consecutive numbers within a row
+    Rows,
+    /// consecutive numbers within a column, columns are read one after another
+    Columns,
+    /// diagonals going down and right in each block of rows, wrapping around to the first column,
+    /// one diagonal starts in every column of the first row of a block
+    Diagonals,
+}
+
+impl Matrix {
+    /// Reads shapes of `sides` numbers each
+    ///
+    /// Fails if the numbers can't be split into whole shapes: the width for [`Layout::Rows`]
+    /// and the amount of rows otherwise have to be a multiple of `sides`.
+    pub fn shapes(&self, sides: usize, layout: Layout) -> Result<Vec<Vec<u64>>> {
+        if sides == 0 {
+            bail!("shapes need at least one side");
+        }
+        let (name, count) = match layout {
+            Layout::Rows => ("columns", self.width),
+            Layout::Columns | Layout::Diagonals => ("rows", self.rows.len()),
+        };
+        if !count.is_multiple_of(sides) {
+            bail!(
+                "reading by {:?} needs a multiple of {} {}, found {} {}",
+                layout,
+                sides,
+                name,
+                count,
+                name
+            );
         }
+        let blocks = self.rows.len() / sides;
+        Ok(match layout {
+            Layout::Rows => self
+                .rows
+                .iter()
+                .flat_map(|row| row.chunks(sides).map(|chunk| chunk.to_vec()))
+                .collect(),
+            Layout::Columns => (0..self.width)
+                .flat_map(|col| {
+                    (0..blocks).map(move |block| {
+                        (0..sides)
+                            .map(|i| self.rows[block * sides + i][col])
+                            .collect()
+                    })
+                })
+                .collect(),
+            Layout::Diagonals => (0..blocks)
+                .flat_map(|block| {
+                    (0..self.width).map(move |col| {
+                        (0..sides)
+                            .map(|i| self.rows[block * sides + i][(col + i) % self.width])
+                            .collect()
+                    })
+                })
+                .collect(),
+        })
     }
+}
 
-    triangles
+/// A polygon is possible if its longest side is shorter than the sum of all other sides.
+pub fn valid_polygon(sides: &[u64]) -> bool {
+    let Some(&longest) = sides.iter().max() else {
+        return false;
+    };
+    let sum: u128 = sides.iter().map(|&side| side as u128).sum();
+    sides.len() >= 3 && sum - longest as u128 > longest as u128
 }
 
 /// In a valid triangle, the sum of any two sides must be larger than the remaining side.
-fn valid_triange(triangle: &[u64; 3]) -> bool {
-    triangle[1] + triangle[2] > triangle[0]
-        && triangle[0] + triangle[2] > triangle[1]
-        && triangle[0] + triangle[1] > triangle[2]
+///
+/// It's enough to check the two shorter sides against the longest one, `a + b > c` is checked as
+/// `a > c - b` so it can't overflow.
+pub fn valid_triange(triangle: &[u64; 3]) -> bool {
+    let [a, b, c] = sorted(triangle);
+    a > c - b
+}
+
+fn sorted(triangle: &[u64; 3]) -> [u64; 3] {
+    let mut sides = *triangle;
+    sides.sort_unstable();
+    sides
+}
+
+/// Classification by the amount of equal sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sides {
+    /// all three sides are equal
+    Equilateral,
+    /// exactly two sides are equal
+    Isosceles,
+    /// no sides are equal
+    Scalene,
+}
+
+/// Classification by the largest angle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Angle {
+    Acute,
+    Right,
+    Obtuse,
+}
+
+/// A possible triangle with its classification and measurements
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    /// side lengths, shortest first
+    pub sides: [u64; 3],
+    pub by_sides: Sides,
+    pub by_angle: Angle,
+    pub perimeter: u128,
+    pub area: f64,
+}
+
+impl Triangle {
+    /// classifies `triangle`, `None` if it's impossible
+    pub fn new(triangle: &[u64; 3]) -> Option<Triangle> {
+        if !valid_triange(triangle) {
+            return None;
+        }
+        let sides = sorted(triangle);
+        let [a, b, c] = sides.map(|side| side as u128);
+        let by_sides = match (a == b, b == c) {
+            (true, true) => Sides::Equilateral,
+            (false, false) => Sides::Scalene,
+            _ => Sides::Isosceles,
+        };
+        // compares a² + b² with c² without overflowing u128
+        let by_angle = match (a * a).cmp(&(c * c - b * b)) {
+            Ordering::Greater => Angle::Acute,
+            Ordering::Equal => Angle::Right,
+            Ordering::Less => Angle::Obtuse,
+        };
+        let perimeter = a + b + c;
+        // numerically stable variant of heron's formula with the longest side first,
+        // see https://en.wikipedia.org/wiki/Heron%27s_formula#Numerical_stability
+        let [c, b, a] = sides.map(|side| side as f64);
+        let area = (a + (b + c)) * (c - (a - b)) * (c + (a - b)) * (a + (b - c));
+        Some(Triangle {
+            sides,
+            by_sides,
+            by_angle,
+            perimeter,
+            area: area.sqrt() / 4.0,
+        })
+    }
+}
+
+/// Counts and totals over all rows of an input
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Summary {
+    pub rows: usize,
+    pub possible: usize,
+    pub by_sides: HashMap<Sides, usize>,
+    pub by_angle: HashMap<Angle, usize>,
+    pub perimeter: u128,
+    pub area: f64,
+    /// the possible triangle with the largest area
+    pub largest: Option<Triangle>,
+}
+
+impl Summary {
+    pub fn new(triangles: &[[u64; 3]]) -> Summary {
+        let mut summary = Summary {
+            rows: triangles.len(),
+            ..Default::default()
+        };
+        for triangle in triangles.iter().filter_map(Triangle::new) {
+            summary.possible += 1;
+            *summary.by_sides.entry(triangle.by_sides).or_default() += 1;
+            *summary.by_angle.entry(triangle.by_angle).or_default() += 1;
+            summary.perimeter += triangle.perimeter;
+            summary.area += triangle.area;
+            if summary
+                .largest
+                .as_ref()
+                .is_none_or(|largest| triangle.area > largest.area)
+            {
+                summary.largest = Some(triangle);
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} triangles are possible",
+            self.possible, self.rows
+        )?;
+        for key in [Sides::Equilateral, Sides::Isosceles, Sides::Scalene] {
+            writeln!(f, "{:?}: {}", key, self.by_sides.get(&key).unwrap_or(&0))?;
+        }
+        for key in [Angle::Acute, Angle::Right, Angle::Obtuse] {
+            writeln!(f, "{:?}: {}", key, self.by_angle.get(&key).unwrap_or(&0))?;
+        }
+        writeln!(f, "total perimeter: {}", self.perimeter)?;
+        writeln!(f, "total area: {:.2}", self.area)?;
+        if let Some(largest) = &self.largest {
+            writeln!(
+                f,
+                "largest: {:?} with area {:.2}",
+                largest.sides, largest.area
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -109,4 +401,220 @@ mod tests {
     fn part1_examples() {
         assert_eq!(part1(&parse_input("5 10 25")), 0);
     }
+
+    const EXAMPLE2: &str = "101 301 501
+102 302 502
+103 303 503
+201 401 601
+202 402 602
+203 403 603";
+
+    #[test]
+    fn part2_examples() {
+        let input = parse_input(EXAMPLE2);
+        // numbers with the same hundreds digit would be part of the same triangle
+        assert_eq!(
+            input.shapes(3, Layout::Columns).unwrap(),
+            vec![
+                vec![101, 102, 103],
+                vec![201, 202, 203],
+                vec![301, 302, 303],
+                vec![401, 402, 403],
+                vec![501, 502, 503],
+                vec![601, 602, 603],
+            ]
+        );
+        assert_eq!(part2(&input), 6);
+        let five_rows = parse_input(&EXAMPLE2[..EXAMPLE2.rfind('\n').unwrap()]);
+        assert_eq!(
+            five_rows
+                .shapes(3, Layout::Columns)
+                .unwrap_err()
+                .to_string(),
+            "reading by Columns needs a multiple of 3 rows, found 5 rows"
+        );
+    }
+
+    #[test]
+    fn shapes() {
+        let matrix = parse_matrix("1 2 3 4\n5 6 7 8").unwrap();
+        assert_eq!(
+            matrix.shapes(2, Layout::Rows).unwrap(),
+            vec![vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8]]
+        );
+        assert_eq!(
+            matrix.shapes(2, Layout::Columns).unwrap(),
+            vec![vec![1, 5], vec![2, 6], vec![3, 7], vec![4, 8]]
+        );
+        assert_eq!(
+            matrix.shapes(2, Layout::Diagonals).unwrap(),
+            vec![vec![1, 6], vec![2, 7], vec![3, 8], vec![4, 5]]
+        );
+        assert_eq!(
+            matrix.shapes(4, Layout::Rows).unwrap(),
+            vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]
+        );
+        assert!(matrix.shapes(3, Layout::Rows).is_err());
+        assert!(matrix.shapes(4, Layout::Columns).is_err());
+        assert!(matrix.shapes(0, Layout::Rows).is_err());
+        assert_eq!(
+            parse_matrix("1 2 3\n4 5").unwrap_err().to_string(),
+            "line 2: expected 3 numbers, found 2"
+        );
+
+        // quadrilaterals: 1 + 2 + 3 > 4 but not 1 + 1 + 1 > 3
+        assert!(valid_polygon(&[4, 1, 2, 3]));
+        assert!(!valid_polygon(&[1, 1, 3, 1]));
+        assert!(valid_polygon(&[u64::MAX; 5]));
+        assert!(!valid_polygon(&[1, 1]));
+        assert!(!valid_polygon(&[]));
+        let matrix = parse_matrix("4 1 2 3 1 1 3 1\n2 2 2 2 9 1 1 1").unwrap();
+        assert_eq!(count_possible(&matrix, 4, Layout::Rows).unwrap(), 2);
+        assert_eq!(count_possible(&matrix, 2, Layout::Columns).unwrap(), 0);
+        assert!(count_possible(&matrix, 0, Layout::Rows).is_err());
+        let matrix = parse_matrix("3 4 5\n3 4 5\n3 4 5\n3 4 5\n3 4 5").unwrap();
+        assert!(count_possible(&matrix, 3, Layout::Columns).is_err());
+
+        let input = parse_input(include_str!("../input/2016/day3.txt"));
+        let triangles = parse_triangles(include_str!("../input/2016/day3.txt")).unwrap();
+        for layout in [Layout::Rows, Layout::Columns, Layout::Diagonals] {
+            for shape in input.shapes(3, layout).unwrap() {
+                assert_eq!(
+                    valid_polygon(&shape),
+                    valid_triange(&[shape[0], shape[1], shape[2]])
+                );
+            }
+        }
+        assert_eq!(part1(&input), Summary::new(&triangles).possible);
+    }
+
+    /// repeats `pattern` `times` times without ever holding more than one copy in memory
+    struct Repeat {
+        pattern: &'static [u8],
+        times: usize,
+        pos: usize,
+    }
+
+    impl std::io::Read for Repeat {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.times == 0 {
+                return Ok(0);
+            }
+            let len = buf.len().min(self.pattern.len() - self.pos);
+            buf[..len].copy_from_slice(&self.pattern[self.pos..self.pos + len]);
+            self.pos += len;
+            if self.pos == self.pattern.len() {
+                self.pos = 0;
+                self.times -= 1;
+            }
+            Ok(len)
+        }
+    }
+
+    const PATTERN: &str =
+        "  5  10  25\n  3\t4 5\n\n101 301 501\n102 302 502\n103 303 503\n 10 1 10\n";
+
+    fn count_repeated(times: usize) -> Counts {
+        let reader = Repeat {
+            pattern: PATTERN.as_bytes(),
+            times,
+            pos: 0,
+        };
+        count_streaming(std::io::BufReader::new(reader)).unwrap()
+    }
+
+    #[test]
+    fn streaming() {
+        let input = include_str!("../input/2016/day3.txt");
+        let counts = count_streaming(input.as_bytes()).unwrap();
+        let matrix = parse_input(input);
+        assert_eq!(counts.rows, input.lines().count());
+        assert_eq!(counts.by_rows, part1(&matrix));
+        assert_eq!(counts.by_columns, part2(&matrix));
+
+        let pattern = parse_input(&PATTERN.repeat(2));
+        let counts = count_repeated(100_000);
+        assert_eq!(counts.rows, 600_000);
+        assert_eq!(counts.by_rows, part1(&pattern) * 50_000);
+        assert_eq!(counts.by_columns, part2(&pattern) * 50_000);
+
+        assert_eq!(
+            count_streaming("3 4 5\n1 2 3\n\n1 2".as_bytes())
+                .unwrap_err()
+                .to_string(),
+            "line 4: expected 3 numbers, found 2"
+        );
+        assert!(count_streaming("3 4 5\n3 4 5".as_bytes()).is_err());
+    }
+
+    /// about 6.6 GB of input, run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn streaming_gigabytes() {
+        let pattern = parse_input(&PATTERN.repeat(2));
+        let counts = count_repeated(100_000_000);
+        assert_eq!(counts.rows, 600_000_000);
+        assert_eq!(counts.by_rows, part1(&pattern) * 50_000_000);
+        assert_eq!(counts.by_columns, part2(&pattern) * 50_000_000);
+    }
+
+    #[test]
+    fn parse_whitespace() {
+        assert_eq!(
+            parse_triangles("  5\t10     25\n\n  3 4\t\t 5  \r\n").unwrap(),
+            vec![[5, 10, 25], [3, 4, 5]]
+        );
+        let err = |input| parse_triangles(input).unwrap_err().to_string();
+        assert_eq!(err("3 4 5\n1 2"), "line 2: expected 3 numbers, found 2");
+        assert_eq!(err("3 4 5\n1 2 3 4"), "line 2: expected 3 numbers, found 4");
+        assert_eq!(err("3 4 5\n\n1 x 3"), "line 3: invalid number \"x\"");
+        assert_eq!(err("-1 2 3"), "line 1: invalid number \"-1\"");
+    }
+
+    #[test]
+    fn classification() {
+        assert!(valid_triange(&[u64::MAX, u64::MAX, u64::MAX]));
+        assert!(valid_triange(&[u64::MAX, u64::MAX, 1]));
+        assert!(!valid_triange(&[u64::MAX, 1, 1]));
+        assert!(!valid_triange(&[1, 2, 3]));
+        assert_eq!(Triangle::new(&[5, 10, 25]), None);
+
+        let right = Triangle::new(&[5, 3, 4]).unwrap();
+        assert_eq!(right.sides, [3, 4, 5]);
+        assert_eq!(right.by_sides, Sides::Scalene);
+        assert_eq!(right.by_angle, Angle::Right);
+        assert_eq!(right.perimeter, 12);
+        assert_eq!(right.area, 6.0);
+
+        let equilateral = Triangle::new(&[2, 2, 2]).unwrap();
+        assert_eq!(equilateral.by_sides, Sides::Equilateral);
+        assert_eq!(equilateral.by_angle, Angle::Acute);
+        assert!((equilateral.area - 3f64.sqrt()).abs() < 1e-12);
+
+        let obtuse = Triangle::new(&[2, 3, 2]).unwrap();
+        assert_eq!(obtuse.by_sides, Sides::Isosceles);
+        assert_eq!(obtuse.by_angle, Angle::Obtuse);
+
+        let huge = Triangle::new(&[u64::MAX, u64::MAX, u64::MAX]).unwrap();
+        assert_eq!(huge.by_angle, Angle::Acute);
+        assert_eq!(huge.perimeter, 3 * u64::MAX as u128);
+        let huge = Triangle::new(&[u64::MAX, u64::MAX, 1]).unwrap();
+        assert_eq!(huge.by_angle, Angle::Acute);
+        assert_eq!(huge.by_sides, Sides::Isosceles);
+    }
+
+    #[test]
+    fn summary() {
+        let summary =
+            Summary::new(&parse_triangles("3 4 5\n5 10 25\n2 2 2\n2 3 2\n6 8 10").unwrap());
+        assert_eq!(summary.rows, 5);
+        assert_eq!(summary.possible, 4);
+        assert_eq!(summary.by_angle[&Angle::Right], 2);
+        assert_eq!(summary.by_sides[&Sides::Scalene], 2);
+        assert_eq!(summary.perimeter, 12 + 6 + 7 + 24);
+        assert_eq!(summary.largest.as_ref().unwrap().sides, [6, 8, 10]);
+        assert!(summary
+            .to_string()
+            .starts_with("4 of 5 triangles are possible\nEquilateral: 1\nIsosceles: 1\n"));
+    }
 }
@@ -40,13 +40,19 @@
 //!
 //! **What is the sector ID of the room where North Pole objects are stored?**
 
+use crate::caesar;
+use crate::cipher::{Cipher, Shift};
+use anyhow::{bail, Result};
 use regex::Regex;
-use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
 
 #[aoc_generator(day4)]
-fn parse_input(input: &str) -> Vec<Room> {
-    let re = Regex::new(r"^(?P<name>[-\w]+)-(?P<sector_id>\d+)\[(?P<checksum>\w+)\]$").unwrap();
+pub fn parse_input(input: &str) -> Vec<Room> {
+    let re = Regex::new(r"^(?P<name>[-a-z]+)-(?P<sector_id>\d+)\[(?P<checksum>\w+)\]$").unwrap();
     input
         .lines()
         .map(|line| {
@@ -76,87 +82,533 @@ fn part1(input: &[Room]) -> u32 {
 /// Part 2: What is the sector ID of the room where North Pole objects are stored?
 #[aoc(day4, part2)]
 fn part2(input: &[Room]) -> u32 {
-    input
-        .iter()
-        .find(|room| decrypt(&room.name, room.sector_id).eq("northpole object storage"))
+    let query = Query::default().regex(Regex::new("^northpole object storage$").unwrap());
+    RoomIndex::new(input)
+        .query(&query)
+        .first()
         .expect("found nothing")
+        .room
         .sector_id
 }
 
-struct Room {
-    name: String,
-    sector_id: u32,
-    checksum: String,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Room {
+    pub name: String,
+    pub sector_id: u32,
+    pub checksum: String,
+}
+
+impl fmt::Display for Room {
+    /// formats the room the way the puzzle input lists it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}[{}]", self.name, self.sector_id, self.checksum)
+    }
+}
+
+/// How ties between letters with the same count are ordered in a checksum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    #[default]
+    Alphabetical,
+    ReverseAlphabetical,
+    /// the letter appearing first in the encrypted name comes first
+    FirstAppearance,
+}
+
+/// Rules for the checksum of a real room, built up like
+/// `ChecksumPolicy::default().length(3).tie_break(TieBreak::FirstAppearance)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumPolicy {
+    length: usize,
+    tie_break: TieBreak,
+    /// which of `a..=z` are counted
+    letters: [bool; 26],
+}
+
+impl ChecksumPolicy {
+    /// the five most common letters, ties broken by alphabetization
+    pub const PUZZLE: ChecksumPolicy = ChecksumPolicy {
+        length: 5,
+        tie_break: TieBreak::Alphabetical,
+        letters: [true; 26],
+    };
+
+    /// number of letters in the checksum, at most 26
+    pub fn length(mut self, length: usize) -> ChecksumPolicy {
+        self.length = length.min(26);
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> ChecksumPolicy {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// counts only these letters
+    pub fn only(mut self, letters: &str) -> ChecksumPolicy {
+        self.letters = [false; 26];
+        for c in letters.bytes().filter(u8::is_ascii_lowercase) {
+            self.letters[(c - b'a') as usize] = true;
+        }
+        self
+    }
+
+    /// counts every letter except these
+    pub fn except(mut self, letters: &str) -> ChecksumPolicy {
+        for c in letters.bytes().filter(u8::is_ascii_lowercase) {
+            self.letters[(c - b'a') as usize] = false;
+        }
+        self
+    }
+}
+
+impl Default for ChecksumPolicy {
+    fn default() -> Self {
+        ChecksumPolicy::PUZZLE
+    }
 }
 
 impl Room {
     /// A room is real (not a decoy) if the checksum is the five most common letters in the
     /// encrypted name, in order, with ties broken by alphabetization.
-    fn is_valid(&self) -> bool {
-        let mut count_map: HashMap<char, u32> = HashMap::new();
-        for c in self.name.replace('-', "").chars() {
-            if let Entry::Vacant(e) = count_map.entry(c) {
-                e.insert(1);
-            } else {
-                let val = count_map.get_mut(&c).unwrap();
-                *val += 1;
+    pub fn is_valid(&self) -> bool {
+        self.is_valid_with(&ChecksumPolicy::PUZZLE)
+    }
+
+    /// the shift cipher the name is encrypted with: decrypting rotates forward by the sector ID,
+    /// so the key is counted backwards
+    pub fn cipher(&self) -> Shift {
+        Shift {
+            key: 26 - self.sector_id % 26,
+        }
+    }
+
+    /// rotates each letter forward through the alphabet a number of times equal to the sector ID,
+    /// dashes become spaces
+    pub fn decrypt(&self) -> String {
+        self.decrypt_with(&self.cipher())
+    }
+
+    /// decrypts the name with another cipher than the puzzle's, dashes become spaces
+    pub fn decrypt_with(&self, cipher: &dyn Cipher) -> String {
+        cipher.decrypt(&self.name.replace('-', " "))
+    }
+
+    /// [`Room::is_valid`] with different rules for the checksum
+    pub fn is_valid_with(&self, policy: &ChecksumPolicy) -> bool {
+        let (checksum, len) = self.checksum_bytes(policy);
+        self.checksum.as_bytes() == &checksum[..len]
+    }
+
+    /// letters of the encrypted name with their counts, most common first with ties broken by
+    /// alphabetization
+    pub fn letter_counts(&self) -> Vec<(char, u32)> {
+        let policy = ChecksumPolicy::PUZZLE;
+        let counts = self.counts(&policy);
+        self.by_count(&counts, &policy)
+            .iter()
+            .filter(|&&letter| counts[letter as usize] > 0)
+            .map(|&letter| ((b'a' + letter) as char, counts[letter as usize]))
+            .collect()
+    }
+
+    /// the five most common letters in the encrypted name, in order, with ties broken by
+    /// alphabetization
+    pub fn compute_checksum(&self) -> String {
+        self.compute_checksum_with(&ChecksumPolicy::PUZZLE)
+    }
+
+    /// [`Room::compute_checksum`] with different rules
+    pub fn compute_checksum_with(&self, policy: &ChecksumPolicy) -> String {
+        let (checksum, len) = self.checksum_bytes(policy);
+        checksum[..len].iter().map(|&c| c as char).collect()
+    }
+
+    /// [`Room::compute_checksum_with`] without allocating, names with less different letters
+    /// than the policy asks for have shorter checksums
+    fn checksum_bytes(&self, policy: &ChecksumPolicy) -> ([u8; 26], usize) {
+        let counts = self.counts(policy);
+        let letters = self.by_count(&counts, policy);
+        let mut checksum = [0; 26];
+        let mut len = 0;
+        for letter in letters.into_iter().take(policy.length) {
+            if counts[letter as usize] == 0 {
+                break;
             }
+            checksum[len] = b'a' + letter;
+            len += 1;
         }
-        let counts: HashSet<u32> = count_map.values().copied().collect();
-        let mut counts: Vec<u32> = counts.iter().copied().collect();
-        counts.sort_unstable();
-        counts.reverse();
-
-        let mut checksum = String::new();
-
-        for count in counts {
-            let mut part = String::new();
-            for c in count_map.keys() {
-                if *count_map.get(c).unwrap() == count {
-                    part += &c.to_string();
-                }
+        (checksum, len)
+    }
+
+    /// how often each letter `a..=z` the policy counts appears in the encrypted name, the
+    /// parsers only accept names made of these letters and dashes
+    fn counts(&self, policy: &ChecksumPolicy) -> [u32; 26] {
+        let mut counts = [0u32; 26];
+        for c in self.name.bytes().filter(u8::is_ascii_lowercase) {
+            let letter = (c - b'a') as usize;
+            if policy.letters[letter] {
+                counts[letter] += 1;
             }
-            checksum += &sort(&part);
-            if checksum.len() > 5 {
-                checksum = checksum[0..5].to_string();
+        }
+        counts
+    }
+
+    /// all letters as offsets from `a`, most common first with ties broken by the policy
+    fn by_count(&self, counts: &[u32; 26], policy: &ChecksumPolicy) -> [u8; 26] {
+        let tie: [u32; 26] = match policy.tie_break {
+            TieBreak::Alphabetical => std::array::from_fn(|letter| letter as u32),
+            TieBreak::ReverseAlphabetical => std::array::from_fn(|letter| 25 - letter as u32),
+            TieBreak::FirstAppearance => {
+                let mut first = [u32::MAX; 26];
+                for (pos, c) in self.name.bytes().enumerate().rev() {
+                    if c.is_ascii_lowercase() {
+                        first[(c - b'a') as usize] = pos as u32;
+                    }
+                }
+                first
             }
-            if checksum.len() == 5 {
-                break;
+        };
+        let mut letters: [u8; 26] = std::array::from_fn(|letter| letter as u8);
+        // the key is unique per counted letter, so this is the order of a stable sort without
+        // the buffer a stable sort allocates
+        letters.sort_unstable_by_key(|&letter| {
+            (Reverse(counts[letter as usize]), tie[letter as usize])
+        });
+        letters
+    }
+}
+
+/// A decoy room with the checksum it would need to be real
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoy<'a> {
+    pub room: &'a Room,
+    pub expected: String,
+    /// see [`Room::letter_counts`]
+    pub counts: Vec<(char, u32)>,
+}
+
+impl fmt::Display for Decoy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |c: char| {
+            let count = self.counts.iter().find(|(other, _)| *other == c);
+            format!("{}={}", c, count.map_or(0, |(_, count)| *count))
+        };
+        let counts: Vec<String> = self.counts.iter().map(|(c, _)| count(*c)).collect();
+        let stated: Vec<String> = self.room.checksum.chars().map(count).collect();
+        write!(
+            f,
+            "{}: stated [{}], correct [{}], letters {}, stated letters {}",
+            self.room,
+            self.room.checksum,
+            self.expected,
+            counts.join(" "),
+            stated.join(" ")
+        )
+    }
+}
+
+/// every room which isn't real, with the checksum it should have and the letter counts which
+/// explain the difference
+pub fn decoys(rooms: &[Room]) -> Vec<Decoy<'_>> {
+    rooms
+        .iter()
+        .filter(|room| !room.is_valid())
+        .map(|room| Decoy {
+            room,
+            expected: room.compute_checksum(),
+            counts: room.letter_counts(),
+        })
+        .collect()
+}
+
+/// rewrites the input with the correct checksum for every room, so no decoys remain
+pub fn repair(rooms: &[Room]) -> String {
+    rooms
+        .iter()
+        .map(|room| {
+            Room {
+                checksum: room.compute_checksum(),
+                ..room.clone()
             }
+            .to_string()
+                + "\n"
+        })
+        .collect()
+}
+
+/// A room with everything known about it, one row of an export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomRecord {
+    pub name: String,
+    pub sector_id: u32,
+    pub checksum: String,
+    pub computed_checksum: String,
+    pub valid: bool,
+    pub decrypted: String,
+}
+
+impl From<&Room> for RoomRecord {
+    fn from(room: &Room) -> Self {
+        RoomRecord {
+            name: room.name.clone(),
+            sector_id: room.sector_id,
+            checksum: room.checksum.clone(),
+            computed_checksum: room.compute_checksum(),
+            valid: room.is_valid(),
+            decrypted: room.decrypt(),
+        }
+    }
+}
+
+impl From<RoomRecord> for Room {
+    /// the room as listed in the input, computed columns are ignored
+    fn from(record: RoomRecord) -> Self {
+        Room {
+            name: record.name,
+            sector_id: record.sector_id,
+            checksum: record.checksum,
         }
-        self.checksum.eq(&checksum)
     }
 }
 
-fn decrypt(name: &str, sector_id: u32) -> String {
-    let mut buffer = String::new();
+const CSV_HEADER: &str = "name,sector_id,checksum,computed_checksum,valid,decrypted";
 
-    let a: u32 = 'a' as u32;
-    let z: u32 = 'z' as u32;
+/// one line per room after a header, see [`RoomRecord`] for the columns
+pub fn export_csv(rooms: &[Room]) -> String {
+    let mut csv = CSV_HEADER.to_string() + "\n";
+    for record in rooms.iter().map(RoomRecord::from) {
+        let fields = [
+            csv_field(&record.name),
+            record.sector_id.to_string(),
+            csv_field(&record.checksum),
+            csv_field(&record.computed_checksum),
+            record.valid.to_string(),
+            csv_field(&record.decrypted),
+        ];
+        csv += &fields.join(",");
+        csv += "\n";
+    }
+    csv
+}
+
+/// quotes the field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// a JSON array of [`RoomRecord`]s
+pub fn export_json(rooms: &[Room]) -> Result<String> {
+    let records: Vec<RoomRecord> = rooms.iter().map(RoomRecord::from).collect();
+    Ok(serde_json::to_string_pretty(&records)?)
+}
+
+/// reads rooms back from [`export_json`]
+pub fn import_json(json: &str) -> Result<Vec<Room>> {
+    let records: Vec<RoomRecord> = serde_json::from_str(json)?;
+    if let Some(record) = records.iter().find(|record| {
+        !record
+            .name
+            .bytes()
+            .all(|c| c == b'-' || c.is_ascii_lowercase())
+    }) {
+        bail!(
+            "room name {:?} may only contain lowercase letters and dashes",
+            record.name
+        );
+    }
+    Ok(records.into_iter().map(Room::from).collect())
+}
 
-    for c in name.chars() {
-        if c == '-' {
-            buffer += " ";
-        } else {
-            let mut n = c as u32 - a;
-            n += sector_id;
-            n %= z - a + 1;
-            n += a;
-            buffer += &(n as u8 as char).to_string();
+/// decrypts a name whose sector ID has been stripped, the recovered `key` is the sector ID
+/// modulo 26 counted backwards: `(26 - key) % 26 == sector_id % 26`
+pub fn recover_name(name: &str) -> caesar::Recovered {
+    caesar::recover(&name.replace('-', " "), caesar::DICTIONARY)
+}
+
+/// What a [`Query`] looks for in the decrypted names
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// the name contains the text
+    Substring(String),
+    Regex(Regex),
+    /// the name contains all of these words, in any order
+    Words(Vec<String>),
+}
+
+impl Pattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Pattern::Substring(text) => name.contains(text.as_str()),
+            Pattern::Regex(re) => re.is_match(name),
+            Pattern::Words(words) => words
+                .iter()
+                .all(|word| name.split(' ').any(|other| other == word)),
         }
     }
-    buffer
 }
 
-fn sort(s: &str) -> String {
-    let mut chars: Vec<char> = s.chars().collect();
-    chars.sort_unstable();
-    String::from_iter(chars)
+/// Which rooms a [`Query`] keeps based on their checksum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Validity {
+    #[default]
+    Any,
+    Real,
+    Decoy,
+}
+
+/// How a [`Query`] sorts its matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// as listed in the input
+    #[default]
+    Input,
+    SectorId,
+    /// by the first word of the decrypted name, rooms sharing it stay in input order
+    FirstWord,
+}
+
+/// Search over the decrypted room names, built up like
+/// `Query::default().substring("north").validity(Validity::Real)`
+#[derive(Debug, Clone)]
+pub struct Query {
+    pattern: Option<Pattern>,
+    validity: Validity,
+    sectors: RangeInclusive<u32>,
+    order: Order,
+}
+
+impl Default for Query {
+    /// matches every room in input order
+    fn default() -> Self {
+        Query {
+            pattern: None,
+            validity: Validity::Any,
+            sectors: 0..=u32::MAX,
+            order: Order::Input,
+        }
+    }
+}
+
+impl Query {
+    pub fn substring(mut self, text: &str) -> Query {
+        self.pattern = Some(Pattern::Substring(text.to_string()));
+        self
+    }
+
+    pub fn regex(mut self, re: Regex) -> Query {
+        self.pattern = Some(Pattern::Regex(re));
+        self
+    }
+
+    pub fn words(mut self, words: &[&str]) -> Query {
+        self.pattern = Some(Pattern::Words(
+            words.iter().map(|word| word.to_string()).collect(),
+        ));
+        self
+    }
+
+    pub fn validity(mut self, validity: Validity) -> Query {
+        self.validity = validity;
+        self
+    }
+
+    pub fn sectors(mut self, sectors: RangeInclusive<u32>) -> Query {
+        self.sectors = sectors;
+        self
+    }
+
+    pub fn order(mut self, order: Order) -> Query {
+        self.order = order;
+        self
+    }
+}
+
+/// A room found by a [`Query`] together with its decrypted name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub room: &'a Room,
+    pub name: &'a str,
+}
+
+impl<'a> Match<'a> {
+    pub fn first_word(&self) -> &'a str {
+        self.name.split(' ').next().unwrap_or_default()
+    }
+}
+
+/// Decrypts the names and validates the checksums of all rooms once, so repeated queries
+/// don't have to
+pub struct RoomIndex<'a> {
+    rooms: &'a [Room],
+    names: Vec<String>,
+    valid: Vec<bool>,
+}
+
+impl<'a> RoomIndex<'a> {
+    pub fn new(rooms: &'a [Room]) -> RoomIndex<'a> {
+        RoomIndex::with_cipher(rooms, Room::cipher)
+    }
+
+    /// for room lists encrypted with another scheme, `cipher` picks the cipher of each room
+    pub fn with_cipher<C: Cipher>(rooms: &'a [Room], cipher: impl Fn(&Room) -> C) -> RoomIndex<'a> {
+        RoomIndex {
+            rooms,
+            names: rooms
+                .iter()
+                .map(|room| room.decrypt_with(&cipher(room)))
+                .collect(),
+            valid: rooms.iter().map(Room::is_valid).collect(),
+        }
+    }
+
+    /// all rooms matching the query, sorted as it asks for
+    pub fn query(&self, query: &Query) -> Vec<Match<'_>> {
+        let mut matches: Vec<Match> = (0..self.rooms.len())
+            .filter(|&idx| match query.validity {
+                Validity::Any => true,
+                Validity::Real => self.valid[idx],
+                Validity::Decoy => !self.valid[idx],
+            })
+            .filter(|&idx| query.sectors.contains(&self.rooms[idx].sector_id))
+            .filter(|&idx| {
+                query
+                    .pattern
+                    .as_ref()
+                    .is_none_or(|pattern| pattern.matches(&self.names[idx]))
+            })
+            .map(|idx| Match {
+                room: &self.rooms[idx],
+                name: &self.names[idx],
+            })
+            .collect();
+        match query.order {
+            Order::Input => {}
+            Order::SectorId => matches.sort_by_key(|m| m.room.sector_id),
+            Order::FirstWord => matches.sort_by(|a, b| a.first_word().cmp(b.first_word())),
+        }
+        matches
+    }
+
+    /// all rooms matching the query, grouped by the first word of their decrypted name
+    pub fn group(&self, query: &Query) -> BTreeMap<&str, Vec<Match<'_>>> {
+        let mut groups: BTreeMap<&str, Vec<Match>> = BTreeMap::new();
+        for m in self.query(query) {
+            groups.entry(m.first_word()).or_default().push(m);
+        }
+        groups
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
+    use crate::cipher::{Affine, RotPerWord, Vigenere};
 
     const EXAMPLE: &str = "aaaaa-bbb-z-y-x-123[abxyz]
 a-b-c-d-e-f-g-h-987[abcde]
@@ -182,7 +634,261 @@ totally-real-room-200[decoy]";
     #[test]
     fn part2_examples() {
         // For example, the real name for `qzmt-zixmtkozy-ivhz-343` is `very encrypted name`.
-        assert_eq!("qzmt zixmtkozy ivhz", decrypt("qzmt-zixmtkozy-ivhz", 0));
-        assert_eq!("very encrypted name", decrypt("qzmt-zixmtkozy-ivhz", 343));
+        let room = |name: &str, sector_id| Room {
+            name: name.to_string(),
+            sector_id,
+            checksum: String::new(),
+        };
+        assert_eq!(
+            "qzmt zixmtkozy ivhz",
+            room("qzmt-zixmtkozy-ivhz", 0).decrypt()
+        );
+        assert_eq!(
+            "very encrypted name",
+            room("qzmt-zixmtkozy-ivhz", 343).decrypt()
+        );
+        assert_eq!("ABC a", room("ZAB-z", 1).decrypt());
+
+        // only the exact name counts, not the same words in another order or with more words
+        let rooms = [
+            room("storage-object-northpole", 26),
+            room("northpole-object-storage-annex", 52),
+            room("northpole-object-storage", 78),
+        ];
+        assert_eq!(part2(&rooms), 78);
+
+        let room = &parse_input("qzmt-zixmtkozy-ivhz-343[zimth]")[0];
+        assert_eq!(room.decrypt_with(&room.cipher()), room.decrypt());
+
+        let recovered = recover_name("qzmt-zixmtkozy-ivhz");
+        assert_eq!(recovered.plaintext, "very encrypted name");
+        assert_eq!((26 - recovered.key) % 26, 343 % 26);
+        let recovered = recover_name(&caesar::encrypt("northpole-object-storage", 5));
+        assert_eq!(recovered.plaintext, "northpole object storage");
+    }
+
+    #[test]
+    fn checksum_policies() {
+        let room = &parse_input("zebra-bar-zoo-1[aborz]")[0];
+        assert!(room.is_valid_with(&ChecksumPolicy::default()));
+        let checksum = |policy: ChecksumPolicy| room.compute_checksum_with(&policy);
+        assert_eq!(checksum(ChecksumPolicy::default()), "aborz");
+        assert_eq!(checksum(ChecksumPolicy::default().length(3)), "abo");
+        assert_eq!(checksum(ChecksumPolicy::default().length(40)), "aborze");
+        assert_eq!(
+            checksum(ChecksumPolicy::default().tie_break(TieBreak::ReverseAlphabetical)),
+            "zroba"
+        );
+        assert_eq!(
+            checksum(ChecksumPolicy::default().tie_break(TieBreak::FirstAppearance)),
+            "zbrao"
+        );
+        assert_eq!(checksum(ChecksumPolicy::default().except("ab")), "orze");
+        assert_eq!(checksum(ChecksumPolicy::default().only("aeiou")), "aoe");
+        assert_eq!(checksum(ChecksumPolicy::default().only("")), "");
+
+        let policy = ChecksumPolicy::default()
+            .length(2)
+            .tie_break(TieBreak::FirstAppearance);
+        let rooms = parse_input("zebra-bar-zoo-1[zb]\nzebra-bar-zoo-1[ab]");
+        assert!(rooms[0].is_valid_with(&policy));
+        assert!(!rooms[1].is_valid_with(&policy));
+    }
+
+    #[test]
+    fn export() {
+        let rooms = parse_input(EXAMPLE);
+        let csv = export_csv(&rooms[2..]);
+        assert_eq!(
+            csv,
+            "name,sector_id,checksum,computed_checksum,valid,decrypted
+not-a-real-room,404,oarel,oarel,true,bch o fsoz fcca
+totally-real-room,200,decoy,loart,false,lglsddq jwsd jgge
+"
+        );
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+
+        let json = export_json(&rooms).unwrap();
+        assert!(json.contains("\"computed_checksum\": \"loart\""));
+        assert!(json.contains("\"decrypted\": \"lglsddq jwsd jgge\""));
+        let imported = import_json(&json).unwrap();
+        assert_eq!(imported, rooms);
+        let lines: Vec<String> = imported.iter().map(Room::to_string).collect();
+        assert_eq!(lines.join("\n"), EXAMPLE);
+
+        assert!(import_json("[{\"name\": \"a\"}]").is_err());
+        let digit = json.replacen("\"aaaaa-bbb-z-y-x\"", "\"aaaaa-bbb-z-y-x1\"", 1);
+        assert!(import_json(&digit).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to parse")]
+    fn name_with_digit() {
+        parse_input("aaaaa-bbb-z-y-x1-123[abxyz]");
+    }
+
+    #[test]
+    fn other_ciphers() {
+        let vigenere = Vigenere::new("bunny").unwrap();
+        let affine = Affine::new(7, 3).unwrap();
+        let rooms: Vec<Room> = ["northpole-object-storage", "candy-coating-research"]
+            .iter()
+            .zip([1, 2])
+            .map(|(name, sector_id)| {
+                let mut room = Room {
+                    name: vigenere.encrypt(name),
+                    sector_id,
+                    checksum: String::new(),
+                };
+                room.checksum = room.compute_checksum();
+                room
+            })
+            .collect();
+        assert!(rooms.iter().all(Room::is_valid));
+        assert_eq!(rooms[0].decrypt_with(&vigenere), "northpole object storage");
+
+        let index = RoomIndex::with_cipher(&rooms, |_| vigenere.clone());
+        let found = index.query(&Query::default().substring("research"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "candy coating research");
+
+        // a different cipher per room
+        let rooms = [
+            Room {
+                name: affine.encrypt("rabbit-design"),
+                sector_id: 1,
+                checksum: String::new(),
+            },
+            Room {
+                name: RotPerWord::new(&[3, 4]).unwrap().encrypt("egg-hunt"),
+                sector_id: 2,
+                checksum: String::new(),
+            },
+        ];
+        let index = RoomIndex::with_cipher(&rooms, |room| -> Box<dyn Cipher> {
+            match room.sector_id {
+                1 => Box::new(affine),
+                _ => Box::new(RotPerWord::new(&[3, 4]).unwrap()),
+            }
+        });
+        let names: Vec<&str> = index
+            .query(&Query::default())
+            .iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names, vec!["rabbit design", "egg hunt"]);
+    }
+
+    #[test]
+    fn queries() {
+        let rooms: Vec<Room> = [
+            ("northpole-object-storage", 26, true),
+            ("candy-coating-research", 27, false),
+            ("candy-storage", 52, false),
+            ("rabbit-object-design", 100, true),
+        ]
+        .iter()
+        .map(|&(name, sector_id, valid)| {
+            let mut room = Room {
+                name: caesar::decrypt(name, sector_id),
+                sector_id,
+                checksum: "decoy".to_string(),
+            };
+            if valid {
+                room.checksum = room.compute_checksum();
+            }
+            room
+        })
+        .collect();
+        let index = RoomIndex::new(&rooms);
+        let ids = |query: Query| -> Vec<u32> {
+            index
+                .query(&query)
+                .iter()
+                .map(|m| m.room.sector_id)
+                .collect()
+        };
+
+        assert_eq!(ids(Query::default()), vec![26, 27, 52, 100]);
+        assert_eq!(ids(Query::default().substring("storage")), vec![26, 52]);
+        assert_eq!(ids(Query::default().substring("age ob")), Vec::<u32>::new());
+        assert_eq!(
+            ids(Query::default().words(&["object", "rabbit"])),
+            vec![100]
+        );
+        assert_eq!(ids(Query::default().words(&["obj"])), Vec::<u32>::new());
+        assert_eq!(
+            ids(Query::default().regex(Regex::new("^candy").unwrap())),
+            vec![27, 52]
+        );
+        assert_eq!(ids(Query::default().sectors(27..=60)), vec![27, 52]);
+        assert_eq!(
+            ids(Query::default().validity(Validity::Decoy)),
+            vec![27, 52]
+        );
+        assert_eq!(
+            ids(Query::default().validity(Validity::Real)),
+            vec![26, 100]
+        );
+        assert_eq!(
+            ids(Query::default().order(Order::FirstWord)),
+            vec![27, 52, 26, 100]
+        );
+        assert_eq!(
+            ids(Query::default()
+                .sectors(27..=100)
+                .order(Order::SectorId)
+                .substring("o")),
+            vec![27, 52, 100]
+        );
+
+        let groups = index.group(&Query::default());
+        assert_eq!(
+            groups.keys().copied().collect::<Vec<_>>(),
+            vec!["candy", "northpole", "rabbit"]
+        );
+        assert_eq!(groups["candy"][1].name, "candy storage");
+        assert_eq!(part2(&rooms), 26);
+    }
+
+    #[test]
+    fn checksums() {
+        let examples = parse_input(EXAMPLE);
+        assert_eq!(examples[0].compute_checksum(), "abxyz");
+        assert_eq!(examples[3].compute_checksum(), "loart");
+        assert_eq!(examples[3].to_string(), "totally-real-room-200[decoy]");
+        assert_eq!(
+            examples[0].letter_counts(),
+            vec![('a', 5), ('b', 3), ('x', 1), ('y', 1), ('z', 1)]
+        );
+
+        let decoys = decoys(&examples);
+        assert_eq!(decoys.len(), 1);
+        assert_eq!(decoys[0].room, &examples[3]);
+        assert_eq!(
+            decoys[0].to_string(),
+            "totally-real-room-200[decoy]: stated [decoy], correct [loart], \
+            letters l=3 o=3 a=2 r=2 t=2 e=1 m=1 y=1, stated letters d=0 e=1 c=0 o=3 y=1"
+        );
+
+        let repaired = repair(&examples);
+        assert_eq!(
+            repaired,
+            EXAMPLE.replace("[decoy]", "[loart]").to_string() + "\n"
+        );
+        assert!(parse_input(&repaired).iter().all(|room| room.is_valid()));
+
+        // less than five different letters
+        let room = &parse_input("ab-ba-b-1[ba]")[0];
+        assert!(room.is_valid());
+        assert_eq!(room.letter_counts(), vec![('b', 3), ('a', 2)]);
+        assert!(!parse_input("ab-ba-b-1[baxyz]")[0].is_valid());
+        assert!(!parse_input("ab-ba-b-1[b]")[0].is_valid());
+
+        // counts don't saturate
+        let room = &parse_input(&format!("{}-{}-1[ba]", "a".repeat(256), "b".repeat(300)))[0];
+        assert_eq!(room.letter_counts(), vec![('b', 300), ('a', 256)]);
+        assert_eq!(room.compute_checksum(), "ba");
+        assert!(room.is_valid());
     }
 }
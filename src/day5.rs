@@ -58,107 +58,227 @@
 //! **Given the actual Door ID and this new method, what is the password?**
 //! Be extra proud of your solution if it uses a cinematic "decrypting" animation.
 
+use crate::random::xorshift;
 use crypto::digest::Digest;
+use rayon::prelude::*;
+use std::io;
+use std::io::Write;
+use std::ops::Range;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// indices one thread hashes at a time
+const CHUNK: u64 = 1 << 10;
+/// indices hashed in parallel before any results are handed out
+const BATCH: u64 = CHUNK * 64;
+/// time between two frames of the decrypting animation
+const FRAME: Duration = Duration::from_millis(40);
 
 /// Part 1: Given the actual Door ID, what is the password?
 #[aoc(day5, part1)]
 fn part1(input: &str) -> String {
-    let mut buffer = String::new();
-    let mut start = 0;
-    for _ in 0..8 {
-        let (next, md5) = md5_suffix_increment_until(input, start, |output| {
-            let first_five = output[0] as i32 + output[1] as i32 + (output[2] >> 4) as i32;
-            first_five == 0
-        });
-        let md5 = format!("{:02x?}", md5[2]);
-        let sixth = &md5[1..2];
-        buffer += sixth;
-        start = next + 1;
-    }
-    buffer
+    InterestingHashes::new(input)
+        .take(8)
+        .map(|(_, md5)| sixth(&md5))
+        .collect()
 }
 
 /// Part 2: Given the actual Door ID and this new method, what is the password?
 #[aoc(day5, part2)]
 fn part2(input: &str) -> String {
-    let mut password: Vec<char> = vec![' '; 8];
-    let mut start = 0;
-    while password.contains(&' ') {
-        let (next, md5) = md5_suffix_increment_until(input, start, |output| {
-            let first_five = output[0] as i32 + output[1] as i32 + (output[2] >> 4) as i32;
-            first_five == 0
+    decrypt(InterestingHashes::new(input), |_, _| true)
+}
+
+/// fills the password of part 2 from the interesting hashes, `progress` is called with the
+/// index and the password so far after every hash and stops the search by returning false
+fn decrypt(
+    hashes: impl Iterator<Item = (u64, [u8; 16])>,
+    mut progress: impl FnMut(u64, &[Option<char>; 8]) -> bool,
+) -> String {
+    let mut password = [None; 8];
+    for (index, md5) in hashes {
+        let complete = fill(&mut password, &md5);
+        if !progress(index, &password) || complete {
+            break;
+        }
+    }
+    password.iter().flatten().collect()
+}
+
+/// Part 2 with a cinematic "decrypting" animation: on a terminal the password is redrawn in
+/// place with unsolved positions cycling through random hex characters, locking in as each
+/// hash is found. Otherwise a progress line is printed for every interesting hash.
+pub fn animate(
+    hashes: impl Iterator<Item = (u64, [u8; 16])> + Send,
+    out: &mut impl Write,
+    terminal: bool,
+) -> io::Result<String> {
+    thread::scope(|scope| {
+        // the receiver is dropped when this closure returns early on a write error, which
+        // makes the next send fail and stops the search before the scope joins it
+        let (sender, receiver) = mpsc::channel();
+        let search = scope.spawn(move || {
+            decrypt(hashes, |index, password| {
+                sender.send((index, *password)).is_ok()
+            })
         });
-        let md5a = format!("{:02x?}", md5[2]);
-        let md5b = format!("{:02x?}", md5[3]);
-        let char_to_put = &md5b[0..1].chars().next().unwrap();
-        let position = &md5a[1..2];
-        match position {
-            "0" => {
-                if password[0] == ' ' {
-                    password[0] = *char_to_put
-                }
-            }
-            "1" => {
-                if password[1] == ' ' {
-                    password[1] = *char_to_put
-                }
-            }
-            "2" => {
-                if password[2] == ' ' {
-                    password[2] = *char_to_put
-                }
-            }
-            "3" => {
-                if password[3] == ' ' {
-                    password[3] = *char_to_put
-                }
-            }
-            "4" => {
-                if password[4] == ' ' {
-                    password[4] = *char_to_put
-                }
-            }
-            "5" => {
-                if password[5] == ' ' {
-                    password[5] = *char_to_put
+        let mut next = xorshift(
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0x2016_0005, |time| time.as_nanos() as u64),
+        );
+        let mut random_hex = || {
+            // dimmed, so locked in characters stand out
+            format!("\x1b[2m{:x}\x1b[0m", next() % 16)
+        };
+        let mut password = [None; 8];
+        loop {
+            match receiver.recv_timeout(FRAME) {
+                Ok((index, found)) => {
+                    password = found;
+                    if !terminal {
+                        writeln!(out, "{index}: {}", render(&password, || "_".to_string()))?;
+                    }
                 }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
             }
-            "6" => {
-                if password[6] == ' ' {
-                    password[6] = *char_to_put
-                }
+            if terminal {
+                write!(out, "\r{}", render(&password, &mut random_hex))?;
+                out.flush()?;
             }
-            "7" => {
-                if password[7] == ' ' {
-                    password[7] = *char_to_put
-                }
-            }
-            _ => {}
         }
-        start = next + 1;
+        if terminal {
+            writeln!(out)?;
+        }
+        Ok(search.join().expect("search failed"))
+    })
+}
+
+/// the password with `unsolved` for every position not found yet
+fn render(password: &[Option<char>; 8], mut unsolved: impl FnMut() -> String) -> String {
+    password
+        .iter()
+        .map(|c| match c {
+            Some(c) => c.to_string(),
+            None => unsolved(),
+        })
+        .collect()
+}
+
+/// both passwords from a single scan over the interesting hashes
+pub fn passwords(door_id: &str) -> (String, String) {
+    let mut first = String::new();
+    let mut second = [None; 8];
+    for (_, md5) in InterestingHashes::new(door_id) {
+        if first.len() < 8 {
+            first.push(sixth(&md5));
+        }
+        if fill(&mut second, &md5) && first.len() == 8 {
+            break;
+        }
     }
-    String::from_iter(password)
+    (first, second.iter().flatten().collect())
+}
+
+/// If it does, the sixth character in the hash is the next character of the password.
+fn sixth(md5: &[u8; 16]) -> char {
+    char::from_digit((md5[2] & 0xf) as u32, 16).unwrap()
+}
+
+/// The sixth character represents the position (`0`-`7`), and the seventh character is the
+/// character to put in that position. Use only the first result for each position, and ignore
+/// invalid positions.
+///
+/// returns true once the password is complete
+fn fill(password: &mut [Option<char>; 8], md5: &[u8; 16]) -> bool {
+    let position = (md5[2] & 0xf) as usize;
+    if position < password.len() && password[position].is_none() {
+        password[position] = char::from_digit((md5[3] >> 4) as u32, 16);
+    }
+    password.iter().all(Option::is_some)
+}
+
+/// the hexadecimal representation of the hash starts with five zeroes
+fn five_zeroes(output: &[u8; 16]) -> bool {
+    let first_five = output[0] as i32 + output[1] as i32 + (output[2] >> 4) as i32;
+    first_five == 0
 }
 
-/// increments a counter starting at 0 which is appended to `input` until `test` returns
-/// true for the md5 hash buffer, then returns the counter
-fn md5_suffix_increment_until(
+/// appends every counter in `range` to `input` and returns those, with their hash, for which
+/// `test` returns true for the md5 hash buffer
+fn md5_suffix_search(
     input: &str,
-    start: u64,
+    range: Range<u64>,
     test: fn(&[u8; 16]) -> bool,
-) -> (u64, [u8; 16]) {
+) -> Vec<(u64, [u8; 16])> {
     let mut hasher = crypto::md5::Md5::new();
     let mut output = [0; 16]; // An MD5 is 16 bytes
-    for i in start..u64::MAX {
+    let mut found = Vec::new();
+    for i in range {
         hasher.input(input.as_bytes());
         hasher.input(i.to_string().as_bytes());
         hasher.result(&mut output);
         if test(&output) {
-            return (i, output);
+            found.push((i, output));
         }
         hasher.reset();
     }
-    (0, output)
+    found
+}
+
+/// Lazily yields every index whose hash with the Door ID starts with five zeroes, together with
+/// the hash, in strict index order. Batches of indices are split into chunks searched on all
+/// threads.
+pub struct InterestingHashes<'a> {
+    door_id: &'a str,
+    test: fn(&[u8; 16]) -> bool,
+    /// first index of the next batch
+    next: u64,
+    found: std::vec::IntoIter<(u64, [u8; 16])>,
+}
+
+impl<'a> InterestingHashes<'a> {
+    pub fn new(door_id: &'a str) -> InterestingHashes<'a> {
+        InterestingHashes::with_test(door_id, 0, five_zeroes)
+    }
+
+    fn with_test(door_id: &'a str, start: u64, test: fn(&[u8; 16]) -> bool) -> Self {
+        InterestingHashes {
+            door_id,
+            test,
+            next: start,
+            found: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Iterator for InterestingHashes<'_> {
+    type Item = (u64, [u8; 16]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(found) = self.found.next() {
+                return Some(found);
+            }
+            if self.next == u64::MAX {
+                return None;
+            }
+            let batch = self.next;
+            let (door_id, test) = (self.door_id, self.test);
+            self.found = (0..BATCH / CHUNK)
+                .into_par_iter()
+                .flat_map_iter(|chunk| {
+                    let from = batch.saturating_add(chunk * CHUNK);
+                    md5_suffix_search(door_id, from..from.saturating_add(CHUNK), test)
+                })
+                .collect::<Vec<_>>()
+                .into_iter();
+            self.next = batch.saturating_add(BATCH);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -179,4 +299,102 @@ mod tests {
         // password `05ace8e3`.
         assert_eq!("05ace8e3", part2(EXAMPLE));
     }
+
+    #[test]
+    fn parallel_search() {
+        // a weaker test than five zeroes to get plenty of results across several batches
+        let test: fn(&[u8; 16]) -> bool = |output| output[0] == 0;
+        let start = 12_345;
+        let end = start + 3 * BATCH + 77;
+        let sequential = md5_suffix_search(EXAMPLE, start..end, test);
+        assert!(sequential.len() > 500);
+        let parallel: Vec<_> = InterestingHashes::with_test(EXAMPLE, start, test)
+            .take_while(|(index, _)| *index < end)
+            .collect();
+        assert_eq!(parallel, sequential);
+
+        let (index, md5) = InterestingHashes::new(EXAMPLE).next().unwrap();
+        assert_eq!(index, 3231929);
+        assert!(five_zeroes(&md5));
+    }
+
+    #[test]
+    fn single_pass() {
+        assert_eq!(
+            passwords(EXAMPLE),
+            ("18f47a30".to_string(), "05ace8e3".to_string())
+        );
+
+        // `abc3231929` produces `0000015...` and `5017308` an invalid position
+        let mut password = [None; 8];
+        let mut hashes = InterestingHashes::new(EXAMPLE);
+        let (_, md5) = hashes.next().unwrap();
+        assert_eq!(sixth(&md5), '1');
+        assert!(!fill(&mut password, &md5));
+        assert_eq!(
+            password,
+            [None, Some('5'), None, None, None, None, None, None]
+        );
+        let (index, md5) = hashes.next().unwrap();
+        assert_eq!(index, 5017308);
+        assert!(!fill(&mut password, &md5));
+        assert_eq!(password.iter().flatten().count(), 1);
+    }
+
+    /// an interesting hash putting `c` at `position`
+    fn hash(position: u8, c: u8) -> [u8; 16] {
+        let mut md5 = [0; 16];
+        md5[2] = position;
+        md5[3] = c << 4;
+        md5
+    }
+
+    #[test]
+    fn animation() {
+        let hashes = [(1, hash(1, 5)), (2, hash(8, 1)), (3, hash(1, 9))]
+            .into_iter()
+            .chain((0..8).map(|position| (10 + position as u64, hash(position, 0xa))));
+
+        let mut out = Vec::new();
+        let password = animate(hashes.clone(), &mut out, false).unwrap();
+        assert_eq!(password, "a5aaaaaa");
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "1: _5______");
+        assert_eq!(lines[1], "2: _5______");
+        assert_eq!(lines[3], "10: a5______");
+        assert_eq!(lines[4], "11: a5______");
+        assert_eq!(lines[10], "17: a5aaaaaa");
+
+        let mut out = Vec::new();
+        let password = animate(hashes, &mut out, true).unwrap();
+        assert_eq!(password, "a5aaaaaa");
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with('\r'));
+        assert!(out.ends_with("\ra5aaaaaa\n"));
+
+        assert_eq!(
+            render(
+                &[Some('0'), None, None, None, None, None, None, Some('7')],
+                || "?".to_string()
+            ),
+            "0??????7"
+        );
+
+        // a closed output, e.g. piped into `head`, stops the search instead of waiting for a
+        // password which is never completed
+        struct Closed;
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let endless = (0..).map(|index| (index, hash(8, 0)));
+        assert!(animate(endless.clone(), &mut Closed, false).is_err());
+        assert!(animate(endless, &mut Closed, true).is_err());
+    }
 }
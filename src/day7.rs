@@ -61,7 +61,7 @@ fn parse_input(input: &str) -> Vec<Ipv7Address> {
                 .collect();
             for part in parts {
                 if part.starts_with('[') {
-                    hypernets.push(part.replace('[', "").replace(']', ""));
+                    hypernets.push(part.replace(['[', ']'], ""));
                 } else {
                     supernets.push(part);
                 }
@@ -169,6 +169,7 @@ fn is_abba(input: &str) -> bool {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -78,7 +78,7 @@ fn decompress1(input: &str) -> String {
     let mut out = String::new();
     let mut marker_start: Option<usize> = None;
     let mut marker_stop: Option<usize> = None;
-    for (idx, char) in input.chars().enumerate() {
+    for (idx, char) in input.char_indices() {
         if marker_start.is_some() && marker_stop.is_none() && char == ')' {
             let (len, cnt) = parse_marker(&input[marker_start.unwrap() + 1..idx]);
             for _ in 0..cnt {
@@ -12,15 +12,18 @@
 extern crate aoc_runner_derive;
 extern crate aoc_runner;
 
-mod day1;
+pub mod caesar;
+pub mod cipher;
+pub mod day1;
 mod day10;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
 mod day6;
 mod day7;
 mod day8;
 mod day9;
+pub mod random;
 
 aoc_lib! { year = 2016 }
@@ -0,0 +1,252 @@
+//! Command line runner for extras which don't fit into `cargo aoc`
+//!
+//! ```bash
+//! cargo run -- 1 --render > day1.svg
+//! cargo run -- 1 --map=city.txt
+//! cargo run -- 2 --keypad=keypad.txt --start=5
+//! cargo run -- 2 --trace
+//! cargo run --release -- 3 --stream huge.txt
+//! cargo run -- 4 --decoys
+//! cargo run -- 4 --repair > repaired.txt
+//! cargo run -- 4 --recover
+//! cargo run -- 4 query northpole --real --sort=sector
+//! cargo run -- 4 --export=json > rooms.json
+//! cargo run -- 4 --import rooms.json
+//! cargo run --release -- 5 --animate
+//! ```
+
+use adventofcode_rust_2016::{day1, day2, day3, day4, day5};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, IsTerminal};
+
+const USAGE: &str = "usage: adventofcode-rust-2016 <day> [options] [input file]
+       adventofcode-rust-2016 4 query <pattern> [options] [input file]
+
+day 1:
+  --render          print the walk as SVG
+  --map=<file>      walk on a city map with closed intersections (`#`) and the start (`S`)
+
+day 2:
+  --keypad=<file>   type the code on a custom keypad layout
+  --start=<key>     key to start on, defaults to `5`
+  --trace           print every move and a heatmap of the keypad, uses the keypad of
+                    part 1 unless `--keypad` is given
+
+day 3:
+  --stream          count possible triangles of both parts in a single pass without loading
+                    the whole input into memory
+
+day 4:
+  --decoys          list every decoy room with its stated and correct checksum
+  --repair          print the input with corrected checksums
+  --recover         decrypt every name without using its sector ID
+  --export=<format> print the rooms as `csv` or `json` with computed checksum, validity and
+                    decrypted name
+  --import          read the input file as JSON from `--export=json` and print the rooms as
+                    the puzzle lists them
+
+day 4 query, prints rooms whose decrypted name contains the pattern:
+  --regex           the pattern is a regular expression
+  --words           the pattern is a list of words which must all appear, in any order
+  --real            only real rooms
+  --decoy           only decoy rooms
+  --sectors=<a-b>   only sector IDs from a to b
+  --sort=<order>    `sector` or `word` (first word of the name), defaults to input order
+  --group           group the rooms by the first word of their name
+
+day 5:
+  --animate         decrypt the password of part 2 with a cinematic animation, prints progress
+                    lines instead when the output is not a terminal";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let day: u32 = match args.first() {
+        Some(day) => day.parse().context(USAGE)?,
+        None => bail!(USAGE),
+    };
+    // `--name` and `--name=value`
+    let options: HashMap<&str, &str> = args[1..]
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("--"))
+        .map(|arg| arg.split_once('=').unwrap_or((arg, "")))
+        .collect();
+    let mut positional: Vec<&str> = args[1..]
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .collect();
+    let pattern = if day == 4 && positional.first() == Some(&"query") {
+        if positional.len() < 2 {
+            bail!(USAGE);
+        }
+        positional.remove(0);
+        Some(positional.remove(0))
+    } else {
+        None
+    };
+    let path = positional
+        .first()
+        .map(|path| path.to_string())
+        .unwrap_or_else(|| format!("input/2016/day{day}.txt"));
+    if day == 3 && options.contains_key("stream") {
+        let file = File::open(&path).with_context(|| format!("failed to read {path}"))?;
+        let counts = day3::count_streaming(BufReader::new(file))?;
+        println!("rows: {}", counts.rows);
+        println!("possible by rows: {}", counts.by_rows);
+        println!("possible by columns: {}", counts.by_columns);
+        return Ok(());
+    }
+    let input = read(&path)?;
+    let input = input.trim();
+
+    match day {
+        1 if options.contains_key("render") => {
+            print!("{}", day1::render_svg(&day1::parse_input(input)))
+        }
+        1 if options.contains_key("map") => {
+            let path = options["map"];
+            let map: day1::CityMap = read(path)?
+                .parse()
+                .with_context(|| format!("invalid city map {path}"))?;
+            let walk = map.walk(day1::Walker::default(), &day1::parse_input(input));
+            for collision in &walk.collisions {
+                println!(
+                    "instruction {} walks through closed intersection {:?}",
+                    collision.instr + 1,
+                    collision.pos
+                );
+            }
+            println!("destination: {:?}", walk.destination);
+            match walk.shortest {
+                Some(shortest) => println!("shortest path: {shortest} blocks"),
+                None => println!("shortest path: unreachable"),
+            }
+        }
+        2 if options.contains_key("keypad") || options.contains_key("trace") => {
+            let start = match options.get("start") {
+                Some(start) if start.chars().count() == 1 => start.chars().next().unwrap(),
+                Some(start) => bail!("start must be a single key, got {:?}", start),
+                None => '5',
+            };
+            let keypad = match options.get("keypad") {
+                Some(path) => day2::Keypad::new(&read(path)?, start)
+                    .with_context(|| format!("invalid keypad {path}"))?,
+                None => day2::Keypad::new(day2::KEYPAD_1, start)?,
+            };
+            let input = day2::parse_input(input);
+            if options.contains_key("trace") {
+                let trace = day2::execute_traced(&input, &keypad);
+                for m in &trace.moves {
+                    let blocked = if m.blocked { " (blocked)" } else { "" };
+                    println!(
+                        "line {}: {} {} {}{}",
+                        m.line + 1,
+                        m.from,
+                        m.instr,
+                        m.to,
+                        blocked
+                    );
+                }
+                print!("{}", keypad.heatmap(&trace));
+                println!("{}", trace.code);
+            } else {
+                println!("{}", day2::execute(&input, &keypad));
+            }
+        }
+        4 if pattern.is_some() => {
+            let rooms = day4::parse_input(input);
+            let index = day4::RoomIndex::new(&rooms);
+            let query = query(pattern.unwrap(), &options)?;
+            if options.contains_key("group") {
+                for (word, matches) in index.group(&query) {
+                    println!("{word}:");
+                    for m in matches {
+                        println!("  {}: {}", m.room, m.name);
+                    }
+                }
+            } else {
+                for m in index.query(&query) {
+                    println!("{}: {}", m.room, m.name);
+                }
+            }
+        }
+        4 if options.contains_key("export") => match options["export"] {
+            "csv" => print!("{}", day4::export_csv(&day4::parse_input(input))),
+            "json" => println!("{}", day4::export_json(&day4::parse_input(input))?),
+            format => bail!("unknown export format {:?}, expected csv or json", format),
+        },
+        4 if options.contains_key("import") => {
+            let rooms = day4::import_json(input).with_context(|| format!("invalid JSON {path}"))?;
+            for room in rooms {
+                println!("{room}");
+            }
+        }
+        4 if options.contains_key("decoys") => {
+            for decoy in day4::decoys(&day4::parse_input(input)) {
+                println!("{decoy}");
+            }
+        }
+        4 if options.contains_key("repair") => {
+            print!("{}", day4::repair(&day4::parse_input(input)))
+        }
+        4 if options.contains_key("recover") => {
+            for room in day4::parse_input(input) {
+                let recovered = day4::recover_name(&room.name);
+                println!("{}: {}", room, recovered.plaintext);
+            }
+        }
+        5 if options.contains_key("animate") => {
+            let mut stdout = std::io::stdout();
+            let terminal = stdout.is_terminal();
+            let password =
+                day5::animate(day5::InterestingHashes::new(input), &mut stdout, terminal)?;
+            if !terminal {
+                println!("{password}");
+            }
+        }
+        _ => bail!(USAGE),
+    }
+    Ok(())
+}
+
+/// builds the day 4 query from the command line
+fn query(pattern: &str, options: &HashMap<&str, &str>) -> Result<day4::Query> {
+    let mut query = day4::Query::default();
+    query = if options.contains_key("regex") {
+        query.regex(Regex::new(pattern).context("invalid regex")?)
+    } else if options.contains_key("words") {
+        query.words(&pattern.split_whitespace().collect::<Vec<_>>())
+    } else {
+        query.substring(pattern)
+    };
+    if options.contains_key("real") {
+        query = query.validity(day4::Validity::Real);
+    } else if options.contains_key("decoy") {
+        query = query.validity(day4::Validity::Decoy);
+    }
+    if let Some(sectors) = options.get("sectors") {
+        let (from, to) = match sectors.split_once('-') {
+            Some((from, to)) => (from.parse(), to.parse()),
+            None => (sectors.parse(), sectors.parse()),
+        };
+        match (from, to) {
+            (Ok(from), Ok(to)) => query = query.sectors(from..=to),
+            _ => bail!("sectors must look like 100-200, got {:?}", sectors),
+        }
+    }
+    query = match options.get("sort") {
+        None => query,
+        Some(&"sector") => query.order(day4::Order::SectorId),
+        Some(&"word") => query.order(day4::Order::FirstWord),
+        Some(order) => bail!("unknown order {:?}, expected sector or word", order),
+    };
+    Ok(query)
+}
+
+fn read(path: &str) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {path}"))
+}
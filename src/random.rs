@@ -0,0 +1,34 @@
+//! # [Xorshift](https://en.wikipedia.org/wiki/Xorshift)
+//!
+//! A tiny deterministic pseudo random number generator for generated inputs, noise and
+//! animations. Not suitable for anything which needs real randomness.
+
+/// Yields pseudo random numbers, the same `seed` always yields the same sequence. A seed of `0`
+/// is replaced by `1`, as xorshift would only ever yield zeroes from it.
+pub fn xorshift(seed: u64) -> impl FnMut() -> u64 {
+    let mut state = seed.max(1);
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences() {
+        let mut a = xorshift(42);
+        let mut b = xorshift(42);
+        let first: Vec<u64> = (0..5).map(|_| a()).collect();
+        assert_eq!(first, (0..5).map(|_| b()).collect::<Vec<_>>());
+        assert_ne!(first[0], first[1]);
+
+        let mut zero = xorshift(0);
+        assert_eq!(zero(), xorshift(1)());
+        assert_ne!(zero(), 0);
+    }
+}